@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use snake::agents::{
+    heuristic::HeuristicConfig,
+    registry::{AgentConfig, AGENTS},
+};
+
+mod common;
+use common::*;
+
+// One decision by a fresh agent, so agents that plan ahead plan every time.
+fn decisions(c: &mut Criterion) {
+    let heuristics = HeuristicConfig::default();
+    for size in SIZES {
+        let game = mid_game(size, 1);
+        let mut group = c.benchmark_group(format!("decision/{}x{}", size, size));
+        for entry in &AGENTS {
+            let mut config = AgentConfig::named(entry.name);
+            // Agents that play from a file are left out.
+            if config.build(&heuristics).is_err() {
+                continue;
+            }
+            // Searching every plan takes minutes on the largest board.
+            if entry.name == "average-path" && size > 20 {
+                config = config.with("budget", 2000);
+            }
+            group.bench_function(config.to_string(), |b| {
+                b.iter_batched(
+                    || config.build(&heuristics).unwrap(),
+                    |mut agent| agent.action(&game),
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = decisions
+}
+criterion_main!(benches);
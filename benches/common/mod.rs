@@ -0,0 +1,17 @@
+use snake::{Coord, Rules, Shape, SnakeGame, Spawn};
+
+/// Board sizes benchmarked, from the default up to the largest that's practical to search.
+pub const SIZES: [Coord; 3] = [10, 20, 40];
+
+/// A game partway through: a coiled snake covering an eighth of the board, placed from `seed`.
+pub fn mid_game(size: Coord, seed: u64) -> SnakeGame {
+    let rules = Rules {
+        starting_length: size as usize * size as usize / 8,
+        ..Default::default()
+    };
+    let spawn = Spawn {
+        shape: Shape::Coiled,
+        wall_margin: 2,
+    };
+    SnakeGame::seeded_with_spawn(size, size, seed, rules, spawn).unwrap()
+}
@@ -0,0 +1,93 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use snake::{compact_words, Action, Cell, Compact, KeyedQueue, SnakeGame, TreeSearch};
+
+mod common;
+use common::*;
+
+// Up to `n` moves from `game` that don't end it, taking the first safe action each time.
+fn safe_moves(game: &SnakeGame, n: usize) -> Vec<Action> {
+    let mut game = game.clone();
+    let mut moves = Vec::new();
+    while moves.len() < n {
+        let Some(action) = Action::iter().find(|a| game.clone().do_action(*a).is_none()) else {
+            break;
+        };
+        let _ = game.do_action(action);
+        moves.push(action);
+    }
+    moves
+}
+
+fn game(c: &mut Criterion) {
+    for size in SIZES {
+        let game = mid_game(size, 1);
+        let moves = safe_moves(&game, 16);
+        let mut group = c.benchmark_group(format!("game/{}x{}", size, size));
+
+        group.bench_function("do_action", |b| {
+            b.iter_batched_ref(
+                || game.clone(),
+                |game| game.do_action(moves[0]),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("do_many/16", |b| b.iter(|| game.do_many(black_box(&moves))));
+        group.bench_function("open_cells", |b| b.iter(|| game.open_cells().count()));
+        group.bench_function("cell_occupant", |b| {
+            b.iter(|| {
+                (0..size)
+                    .flat_map(|x| (0..size).map(move |y| Cell(x, y)))
+                    .filter(|c| game.cell_occupant(*c).is_some())
+                    .count()
+            })
+        });
+        // Sized for the largest board, so every size uses the same type.
+        type Packed = Compact<{ compact_words(40, 40) }>;
+        let packed = Packed::try_from(&game).unwrap();
+        group.bench_function("compact/pack", |b| {
+            b.iter(|| Packed::try_from(black_box(&game)))
+        });
+        group.bench_function("compact/unpack", |b| {
+            b.iter(|| black_box(&packed).to_game(game.rules, 0))
+        });
+        group.finish();
+    }
+}
+
+fn search(c: &mut Criterion) {
+    const N: usize = 10_000;
+    let keys = {
+        let mut rng = SmallRng::seed_from_u64(0);
+        (0..N).map(|_| rng.gen::<u32>()).collect::<Vec<_>>()
+    };
+
+    let mut group = c.benchmark_group("search");
+    group.throughput(Throughput::Elements(N as u64));
+    group.bench_function("keyed_queue/push_pop", |b| {
+        b.iter(|| {
+            let mut queue = KeyedQueue::new();
+            for (i, key) in keys.iter().enumerate() {
+                queue.insert(*key, i);
+            }
+            while let Some(i) = queue.pop() {
+                black_box(i);
+            }
+        })
+    });
+    // A ternary tree ranked by a hash of each node, so the search jumps around the frontier.
+    group.bench_function("tree_search/nodes", |b| {
+        b.iter(|| {
+            let search = TreeSearch::new(
+                1u64,
+                |n| n.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32,
+                |n| (0..3).map(|i| n * 3 + i).collect(),
+            );
+            search.take(N).count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, game, search);
+criterion_main!(benches);
@@ -1,15 +1,19 @@
-use super::Agent;
+use super::{heuristic::Weights, surviving_action, Agent};
 use crate::{gameplay::*, TreeSearch};
 
 use float_ord::FloatOrd;
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, VecDeque},
-};
+use std::{cmp::Reverse, collections::VecDeque};
 
-#[derive(Default)]
 pub struct AveragePath {
     plan: VecDeque<Action>,
+    weights: Weights,
+    budget: Option<usize>,
+}
+
+impl Default for AveragePath {
+    fn default() -> Self {
+        AveragePath::with_weights(Weights::average_path())
+    }
 }
 
 impl Agent for AveragePath {
@@ -21,13 +25,38 @@ impl Agent for AveragePath {
         self.plan = self.generate_plan(game);
         self.plan.pop_front().unwrap()
     }
+
+    fn plan(&self) -> Option<Vec<Action>> {
+        Some(self.plan.iter().copied().collect())
+    }
+
+    fn reset(&mut self) {
+        self.plan.clear();
+    }
 }
 
 impl AveragePath {
+    pub fn with_weights(weights: Weights) -> Self {
+        AveragePath {
+            plan: VecDeque::new(),
+            weights,
+            budget: None,
+        }
+    }
+
+    // Limits how many partial plans are searched per apple, keeping the best found so far.
+    pub fn with_budget(self, budget: usize) -> Self {
+        AveragePath {
+            budget: Some(budget),
+            ..self
+        }
+    }
+
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
     fn generate_plan(&self, game: &SnakeGame) -> VecDeque<Action> {
         let search = TreeSearch::new(
             VecDeque::new(),
-            |actions| Reverse(best_case_score(&actions, game)),
+            |actions| Reverse(self.best_case_score(actions, game)),
             |actions| {
                 if game.do_many(actions).unwrap().score > game.score {
                     return Vec::new();
@@ -42,23 +71,42 @@ impl AveragePath {
 
         let must_be_better_than = std::cell::Cell::new(FloatOrd(f32::INFINITY));
 
-        let result = search
-            .take_while(|actions| best_case_score(&actions, game) <= must_be_better_than.get())
+        search
+            .take(self.budget.unwrap_or(usize::MAX))
+            .take_while(|actions| self.best_case_score(actions, game) <= must_be_better_than.get())
             .filter(|actions| game.do_many(actions).unwrap().score > game.score)
             .min_by_key(|actions| {
-                let score = actual_score(&actions, game);
+                let score = self.actual_score(actions, game);
                 must_be_better_than.set(std::cmp::min(score, must_be_better_than.get()));
                 score
             })
-            .expect("No path to apple");
-        result
+            .unwrap_or_else(|| [surviving_action(game)].into_iter().collect())
+    }
+
+    // Lower bound of `actual_score` for any plan starting with `actions`. Only holds for weights
+    // that pass `Weights::check_pruning`.
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
+    fn best_case_score(&self, actions: &VecDeque<Action>, game: &SnakeGame) -> FloatOrd<f32> {
+        let w = &self.weights;
+        let new_game = game.do_many(actions).unwrap();
+        let best_case_moves =
+            actions.len() + new_game.head().taxicab_distance_to(game.apple) as usize;
+        let max_distance = game.width as f32 + game.height as f32;
+        FloatOrd(
+            w.apples_eaten
+                + w.path_length * best_case_moves as f32
+                + w.average_moves * best_case_average_moves(&new_game)
+                + w.distance_to_apple.min(0.0) * max_distance
+                + w.fraction_lower_bound(),
+        )
     }
-}
 
-fn best_case_score(actions: &VecDeque<Action>, game: &SnakeGame) -> FloatOrd<f32> {
-    let new_game = game.do_many(actions).unwrap();
-    let best_case_moves = actions.len() + new_game.head().taxicab_distance_to(game.apple) as usize;
-    FloatOrd(best_case_moves as f32 + best_case_average_moves(&new_game))
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
+    fn actual_score(&self, actions: &VecDeque<Action>, game: &SnakeGame) -> FloatOrd<f32> {
+        let new_game = game.do_many(actions).unwrap();
+        assert_eq!(new_game.head(), game.apple);
+        FloatOrd(self.weights.cost(game, &new_game, actions.len()))
+    }
 }
 
 fn best_case_average_moves(game: &SnakeGame) -> f32 {
@@ -70,42 +118,3 @@ fn best_case_average_moves(game: &SnakeGame) -> f32 {
 
     move_counts.iter().sum::<usize>() as f32 / move_counts.len() as f32
 }
-
-fn actual_score(actions: &VecDeque<Action>, game: &SnakeGame) -> FloatOrd<f32> {
-    let new_game = game.do_many(actions).unwrap();
-    assert_eq!(new_game.head(), game.apple);
-    FloatOrd(actions.len() as f32 + average_moves(&new_game))
-}
-
-// Average number of moves required to reach open cells.
-fn average_moves(game: &SnakeGame) -> f32 {
-    let mut front = VecDeque::new();
-    front.push_back(game.head());
-
-    let mut min_steps: HashMap<Cell, usize> = HashMap::new();
-    while let Some(cell) = front.pop_front() {
-        let steps = *min_steps.get(&cell).unwrap_or(&0);
-        for neighbor in cell.neighbors() {
-            match game.cell_occupant(neighbor) {
-                None | Some(Occupant::Apple) => {}
-                Some(Occupant::Body) => continue,
-            }
-            if min_steps.contains_key(&neighbor) {
-                continue;
-            }
-            front.push_back(neighbor);
-            min_steps.insert(neighbor, steps + 1);
-        }
-    }
-
-    let total = game
-        .non_body_cells()
-        .map(|cell| {
-            min_steps
-                .get(&cell)
-                .map(|steps| *steps as f32)
-                .unwrap_or(f32::INFINITY)
-        })
-        .sum::<f32>();
-    total / game.non_body_cells().count() as f32
-}
@@ -0,0 +1,353 @@
+use super::{heuristic::HeuristicConfig, par_map, play_out, registry::AgentConfig};
+use crate::gameplay::*;
+
+use rand::{prelude::*, rngs::SmallRng};
+use std::fmt;
+
+/// A paired comparison of two agents: both play the same seeded boards, and each metric is
+/// compared on the per-seed differences, `b` minus `a`.
+///
+/// Intervals are bootstrap percentile intervals of the mean difference, and p-values come from a
+/// sign-flip permutation test, which needs no assumptions about how scores are distributed. Both
+/// are seeded, so the same games give the same report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub width: Coord,
+    pub height: Coord,
+    pub games: usize,
+    pub seed: u64,
+    pub max_moves_without_apple: usize,
+    /// Confidence level is `1 - alpha`, and differences with `p < alpha` are significant.
+    pub alpha: f64,
+    /// How much worse than `a`, as a fraction of `a`'s mean, `b` has to be to count as a
+    /// regression. Only significant differences count.
+    pub threshold: f64,
+    pub resamples: usize,
+    pub threads: usize,
+}
+
+/// How one agent did on one board.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Game {
+    pub score: usize,
+    pub moves: usize,
+    pub won: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: &'static str,
+    pub higher_is_better: bool,
+    pub a: f64,
+    pub b: f64,
+    /// Mean of `b - a` over the pairs.
+    pub difference: f64,
+    pub interval: (f64, f64),
+    pub p_value: f64,
+    /// Seeds the metric is defined on for both agents.
+    pub pairs: usize,
+    pub regression: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub a: String,
+    pub b: String,
+    pub confidence: f64,
+    pub metrics: Vec<Metric>,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Comparison {
+            width: 10,
+            height: 10,
+            games: 50,
+            seed: 0,
+            max_moves_without_apple: 500,
+            alpha: 0.05,
+            threshold: 0.05,
+            resamples: 10_000,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Comparison {
+    pub fn run(
+        &self,
+        a: &AgentConfig,
+        b: &AgentConfig,
+        heuristics: &HeuristicConfig,
+    ) -> Result<Report, String> {
+        a.build(heuristics)?;
+        b.build(heuristics)?;
+        let (games_a, games_b) = (self.play(a, heuristics), self.play(b, heuristics));
+        Ok(self.analyze(&a.to_string(), &games_a, &b.to_string(), &games_b))
+    }
+
+    // One game per seed, in seed order.
+    fn play(&self, config: &AgentConfig, heuristics: &HeuristicConfig) -> Vec<Game> {
+        let seeds = (0..self.games as u64)
+            .map(|i| self.seed.wrapping_add(i))
+            .collect::<Vec<_>>();
+        par_map(&seeds, self.threads, |seed| {
+            let mut agent = config.build(heuristics).unwrap();
+            let mut game = SnakeGame::seeded(self.width, self.height, *seed);
+            let end = play_out(agent.as_mut(), &mut game, self.max_moves_without_apple);
+            Game {
+                score: game.score,
+                moves: game.moves,
+                won: end == Some(Terminal::Won),
+            }
+        })
+    }
+
+    /// Compares games played on the same seeds, in the same order.
+    pub fn analyze(&self, a_name: &str, a: &[Game], b_name: &str, b: &[Game]) -> Report {
+        assert_eq!(a.len(), b.len(), "games have to be paired");
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+
+        type Measure = fn(&Game) -> Option<f64>;
+        let measures: [(&str, bool, Measure); 3] = [
+            ("win rate", true, |g| Some(g.won as u8 as f64)),
+            ("score", true, |g| Some(g.score as f64)),
+            // Only defined once an apple is eaten.
+            ("moves per apple", false, |g| {
+                (g.score > 0).then(|| g.moves as f64 / g.score as f64)
+            }),
+        ];
+        let metrics = measures
+            .into_iter()
+            .map(|(name, higher_is_better, measure)| {
+                let pairs = a
+                    .iter()
+                    .zip(b)
+                    .filter_map(|(a, b)| Some((measure(a)?, measure(b)?)))
+                    .collect::<Vec<_>>();
+                let differences = pairs.iter().map(|(a, b)| b - a).collect::<Vec<_>>();
+                let mean_a = mean(pairs.iter().map(|p| p.0));
+                let difference = mean(differences.iter().copied());
+                let p_value = sign_flip_p(&differences, self.resamples, &mut rng);
+
+                let worse_by = if higher_is_better {
+                    -difference
+                } else {
+                    difference
+                };
+                Metric {
+                    name,
+                    higher_is_better,
+                    a: mean_a,
+                    b: mean(pairs.iter().map(|p| p.1)),
+                    difference,
+                    interval: bootstrap_interval(
+                        &differences,
+                        self.alpha,
+                        self.resamples,
+                        &mut rng,
+                    ),
+                    p_value,
+                    pairs: pairs.len(),
+                    regression: p_value < self.alpha && worse_by > self.threshold * mean_a.abs(),
+                }
+            })
+            .collect();
+
+        Report {
+            a: a_name.to_string(),
+            b: b_name.to_string(),
+            confidence: 1.0 - self.alpha,
+            metrics,
+        }
+    }
+}
+
+impl Report {
+    pub fn regressed(&self) -> bool {
+        self.metrics.iter().any(|m| m.regression)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "a: {}\nb: {}", self.a, self.b)?;
+        writeln!(
+            f,
+            "{:<16} {:>9} {:>9} {:>9}  {:<21} {:>7} {:>6}",
+            "metric",
+            "a",
+            "b",
+            "b - a",
+            format!("{:.0}% interval", self.confidence * 100.0),
+            "p",
+            "pairs"
+        )?;
+        for m in &self.metrics {
+            let verdict = match (m.regression, m.p_value < 1.0 - self.confidence) {
+                (true, _) => "  REGRESSION",
+                (false, true) if (m.difference > 0.0) == m.higher_is_better => "  better",
+                (false, true) => "  worse",
+                (false, false) => "",
+            };
+            writeln!(
+                f,
+                "{:<16} {:>9.3} {:>9.3} {:>+9.3}  [{:>+8.3}, {:>+8.3}]  {:>7.4} {:>6}{}",
+                m.name,
+                m.a,
+                m.b,
+                m.difference,
+                m.interval.0,
+                m.interval.1,
+                m.p_value,
+                m.pairs,
+                verdict
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+    if n == 0 {
+        0.0
+    } else {
+        sum / n as f64
+    }
+}
+
+// Percentile interval of the mean of `differences` over resamples drawn with replacement.
+fn bootstrap_interval(
+    differences: &[f64],
+    alpha: f64,
+    resamples: usize,
+    rng: &mut impl Rng,
+) -> (f64, f64) {
+    if differences.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mut means = (0..resamples.max(1))
+        .map(|_| mean((0..differences.len()).map(|_| *differences.choose(rng).unwrap())))
+        .collect::<Vec<_>>();
+    means.sort_by(f64::total_cmp);
+    let at = |q: f64| means[((means.len() - 1) as f64 * q).round() as usize];
+    (at(alpha / 2.0), at(1.0 - alpha / 2.0))
+}
+
+// Two-sided p-value for the mean difference being zero. If the agents were interchangeable each
+// difference would be as likely to have either sign, so this counts how often random signs give a
+// mean at least as far from zero.
+fn sign_flip_p(differences: &[f64], resamples: usize, rng: &mut impl Rng) -> f64 {
+    let observed = mean(differences.iter().copied()).abs();
+    if observed == 0.0 {
+        return 1.0;
+    }
+    let as_extreme = (0..resamples)
+        .filter(|_| {
+            let flipped = differences.iter().map(|d| if rng.gen() { *d } else { -d });
+            // Allow for rounding in sums that are equal in exact arithmetic.
+            mean(flipped).abs() >= observed - 1e-12
+        })
+        .count();
+    // Counting the observed signs too keeps the p-value above zero.
+    (as_extreme + 1) as f64 / (resamples + 1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn games(scores: &[usize]) -> Vec<Game> {
+        scores
+            .iter()
+            .map(|&score| Game {
+                score,
+                moves: score * 10,
+                won: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_agents_differ_by_nothing() {
+        let comparison = Comparison::default();
+        let a = games(&[3, 8, 5, 0, 12]);
+        let report = comparison.analyze("a", &a, "b", &a);
+        for metric in &report.metrics {
+            assert_eq!(metric.difference, 0.0);
+            assert_eq!(metric.interval, (0.0, 0.0));
+            assert_eq!(metric.p_value, 1.0);
+            assert!(!metric.regression);
+        }
+        // Moves per apple skips the game without an apple.
+        assert_eq!(report.metrics[2].pairs, 4);
+    }
+
+    #[test]
+    fn flags_consistent_drops() {
+        let comparison = Comparison {
+            resamples: 2_000,
+            ..Default::default()
+        };
+        let a = games(&(10..40).collect::<Vec<_>>());
+        let b = games(&(8..38).collect::<Vec<_>>());
+        let report = comparison.analyze("a", &a, "b", &b);
+        let score = &report.metrics[1];
+        assert_eq!(score.difference, -2.0);
+        assert!(score.p_value < 0.01);
+        assert_eq!(score.interval, (-2.0, -2.0));
+        assert!(score.regression && report.regressed());
+        // Ten moves per apple either way.
+        assert!(!report.metrics[2].regression);
+        assert!(report.to_string().contains("REGRESSION"));
+
+        // The same drop with the agents swapped is an improvement.
+        let report = comparison.analyze("b", &b, "a", &a);
+        assert!(!report.regressed());
+        assert!(report.to_string().contains("better"));
+    }
+
+    #[test]
+    fn small_or_noisy_drops_pass() {
+        let comparison = Comparison {
+            resamples: 2_000,
+            ..Default::default()
+        };
+        // Significant, but only 1% worse.
+        let a = games(&(100..130).collect::<Vec<_>>());
+        let b = games(&(99..129).collect::<Vec<_>>());
+        assert!(!comparison.analyze("a", &a, "b", &b).regressed());
+
+        // Worse on average, but no more often than better.
+        let a = games(&[10, 20, 10, 20, 10, 20]);
+        let b = games(&[20, 10, 20, 10, 0, 10]);
+        let report = comparison.analyze("a", &a, "b", &b);
+        assert!(report.metrics[1].p_value > 0.05);
+        assert!(!report.regressed());
+    }
+
+    #[test]
+    fn plays_both_agents_on_the_same_seeds() {
+        let comparison = Comparison {
+            width: 5,
+            height: 5,
+            games: 4,
+            resamples: 100,
+            max_moves_without_apple: 50,
+            ..Default::default()
+        };
+        let config = AgentConfig::named("tree-search");
+        let report = comparison
+            .run(&config, &config, &HeuristicConfig::default())
+            .unwrap();
+        assert!(report.metrics.iter().all(|m| m.difference == 0.0));
+        assert!(comparison
+            .run(
+                &config,
+                &AgentConfig::named("nobody"),
+                &HeuristicConfig::default()
+            )
+            .is_err());
+    }
+}
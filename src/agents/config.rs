@@ -0,0 +1,198 @@
+//! The TOML files agents, heuristics and sweeps are configured with, read as `key = value`
+//! entries:
+//!
+//! ```toml
+//! key = "a string"
+//! [section]
+//! key = 0.5
+//! key = [1, "two", 3]
+//! ```
+//!
+//! Tables are sections, and only go one level deep. Every value is kept as text for the reader to
+//! parse, and a lone value is a list of one.
+
+use std::{fmt, ops::Range, str::FromStr};
+use toml::{
+    de::{DeString, DeTable, DeValue},
+    Spanned,
+};
+
+/// A `key = value` line, with the section it's in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Counting from 1, for errors.
+    pub line: usize,
+    pub section: Option<String>,
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    One(String),
+    List(Vec<String>),
+}
+
+impl Entry {
+    /// `message` prefixed with the entry's line.
+    pub fn error(&self, message: impl fmt::Display) -> String {
+        format!("line {}: {}", self.line, message)
+    }
+
+    pub fn text(&self) -> Result<&str, String> {
+        match &self.value {
+            Value::One(value) => Ok(value),
+            Value::List(_) => Err(self.error(format!("`{}` takes a single value", self.key))),
+        }
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        match &self.value {
+            Value::One(value) => vec![value.clone()],
+            Value::List(values) => values.clone(),
+        }
+    }
+
+    pub fn number<T: FromStr>(&self) -> Result<T, String> {
+        let text = self.text()?;
+        text.parse()
+            .map_err(|_| self.error(format!("invalid number {:?}", text)))
+    }
+}
+
+/// Every entry in `text`, in order.
+pub fn parse(text: &str) -> Result<Vec<Entry>, String> {
+    let document = DeTable::parse(text).map_err(|e| match e.span() {
+        Some(span) => format!("line {}: {}", line(text, span), e.message()),
+        None => e.message().to_string(),
+    })?;
+
+    let mut entries = Vec::new();
+    for (key, value) in document.get_ref() {
+        match value.get_ref() {
+            DeValue::Table(section) => {
+                for (name, value) in section {
+                    entries.push(entry(text, Some(key.get_ref()), name, value.get_ref())?);
+                }
+            }
+            value => entries.push(entry(text, None, key, value)?),
+        }
+    }
+    // Tables keep their keys sorted, so entries are put back in the order they're written in.
+    entries.sort_by_key(|entry| entry.line);
+    Ok(entries)
+}
+
+fn entry(
+    text: &str,
+    section: Option<&str>,
+    key: &Spanned<DeString>,
+    value: &DeValue,
+) -> Result<Entry, String> {
+    let line = line(text, key.span());
+    let at = |e: String| format!("line {}: {}", line, e);
+    let value = match value {
+        DeValue::Array(items) => Value::List(
+            items
+                .iter()
+                .map(|item| scalar(item.get_ref()).map_err(at))
+                .collect::<Result<_, _>>()?,
+        ),
+        value => Value::One(scalar(value).map_err(at)?),
+    };
+    Ok(Entry {
+        line,
+        section: section.map(str::to_string),
+        key: key.get_ref().to_string(),
+        value,
+    })
+}
+
+// Counting from 1, of the line `span` starts on.
+fn line(text: &str, span: Range<usize>) -> usize {
+    text[..span.start].matches('\n').count() + 1
+}
+
+fn scalar(value: &DeValue) -> Result<String, String> {
+    Ok(match value {
+        DeValue::String(s) => s.to_string(),
+        DeValue::Integer(i) => i64::from_str_radix(i.as_str(), i.radix())
+            .map_err(|_| format!("integer {} out of range", i))?
+            .to_string(),
+        DeValue::Float(f) => f.as_str().to_string(),
+        DeValue::Boolean(b) => b.to_string(),
+        DeValue::Datetime(d) => d.to_string(),
+        DeValue::Array(_) => return Err("lists can't be nested".to_string()),
+        DeValue::Table(_) => return Err("sections can't be nested".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_entries_in_order() {
+        let text = "# A comment.\n\
+                    name = \"tree-search\"\n\
+                    [section]\n\
+                    key = \"a string, which may hold # and \\\" and \\\\\"\n\
+                    b = 0.5 # trailing\n\
+                    a = [1, \"two, three\", 3]\n\
+                    empty = []\n";
+        let entries = parse(text).unwrap();
+        let values = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.line,
+                    e.section.as_deref(),
+                    e.key.as_str(),
+                    e.value.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let one = |s: &str| Value::One(s.to_string());
+        let list = |items: &[&str]| Value::List(items.iter().map(|s| s.to_string()).collect());
+        assert_eq!(
+            values,
+            [
+                (2, None, "name", one("tree-search")),
+                (
+                    4,
+                    Some("section"),
+                    "key",
+                    one("a string, which may hold # and \" and \\")
+                ),
+                (5, Some("section"), "b", one("0.5")),
+                (6, Some("section"), "a", list(&["1", "two, three", "3"])),
+                (7, Some("section"), "empty", list(&[])),
+            ]
+        );
+        assert_eq!(entries[2].number::<f32>(), Ok(0.5));
+        assert!(entries[3].text().unwrap_err().starts_with("line 6:"));
+        assert_eq!(entries[0].list(), ["tree-search"]);
+    }
+
+    #[test]
+    fn rejects_what_isnt_toml() {
+        for (text, line) in [
+            ("a = 1\nb", 2),
+            ("a = \"open", 1),
+            ("a = tree-search", 1),
+            ("a = [1, 2", 1),
+            ("a = [[1], [2]]", 1),
+            ("\n[a]\nb = { c = 1 }", 3),
+            ("a = 1\na = 2", 2),
+            ("a =", 1),
+        ] {
+            let err = parse(text).unwrap_err();
+            assert!(
+                err.starts_with(&format!("line {}:", line)),
+                "{:?}: {}",
+                text,
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use crate::gameplay::*;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io,
+    path::Path,
+};
+
+/// Weighted sum of position features, used by search agents as a cost to minimize.
+///
+/// Features other than `apples_eaten`, `path_length`, `average_moves` and `distance_to_apple`
+/// are fractions in `0..=1`, so negative weights reward them and positive weights penalize them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub apples_eaten: f32,
+    pub path_length: f32,
+    pub average_moves: f32,
+    pub distance_to_apple: f32,
+    pub reachable_area: f32,
+    pub tail_reachable: f32,
+    pub compactness: f32,
+    pub wall_contact: f32,
+}
+
+impl Weights {
+    /// Each weight's name and what it's the cost of, in `to_array` order.
+    pub const ABOUT: [(&'static str, &'static str); 8] = [
+        ("apples_eaten", "cost per apple eaten along the plan"),
+        ("path_length", "cost per move in the plan"),
+        (
+            "average_moves",
+            "cost per move it takes on average to reach the open cells",
+        ),
+        (
+            "distance_to_apple",
+            "cost per cell between the head and the apple",
+        ),
+        (
+            "reachable_area",
+            "cost of the fraction of open cells the head can reach",
+        ),
+        (
+            "tail_reachable",
+            "cost of the head being able to reach the tail",
+        ),
+        (
+            "compactness",
+            "cost of the fraction of body sides touching the body",
+        ),
+        (
+            "wall_contact",
+            "cost of the fraction of the body along a wall",
+        ),
+    ];
+
+    pub const NAMES: [&'static str; 8] = {
+        let mut names = [""; 8];
+        let mut i = 0;
+        while i < names.len() {
+            names[i] = Weights::ABOUT[i].0;
+            i += 1;
+        }
+        names
+    };
+
+    pub fn zero() -> Weights {
+        Weights::from_array([0.0; 8])
+    }
+
+    pub fn average_path() -> Weights {
+        Weights {
+            path_length: 1.0,
+            average_moves: 1.0,
+            ..Weights::zero()
+        }
+    }
+
+    // Approximates the original score > distance > path length ordering.
+    pub fn tree_search() -> Weights {
+        Weights {
+            apples_eaten: -1_000_000.0,
+            distance_to_apple: 1_000.0,
+            path_length: 1.0,
+            ..Weights::zero()
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 8] {
+        [
+            self.apples_eaten,
+            self.path_length,
+            self.average_moves,
+            self.distance_to_apple,
+            self.reachable_area,
+            self.tail_reachable,
+            self.compactness,
+            self.wall_contact,
+        ]
+    }
+
+    pub fn from_array(a: [f32; 8]) -> Weights {
+        Weights {
+            apples_eaten: a[0],
+            path_length: a[1],
+            average_moves: a[2],
+            distance_to_apple: a[3],
+            reachable_area: a[4],
+            tail_reachable: a[5],
+            compactness: a[6],
+            wall_contact: a[7],
+        }
+    }
+
+    pub(super) fn set(&mut self, name: &str, value: f32) -> bool {
+        match Weights::NAMES.iter().position(|n| *n == name) {
+            Some(i) => {
+                let mut array = self.to_array();
+                array[i] = value;
+                *self = Weights::from_array(array);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cost of reaching `game` from `origin` by a path of `path_length` actions.
+    ///
+    /// Features with a zero weight are not computed.
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
+    pub fn cost(&self, origin: &SnakeGame, game: &SnakeGame, path_length: usize) -> f32 {
+        let mut cost = self.apples_eaten * (game.score - origin.score) as f32
+            + self.path_length * path_length as f32;
+
+        if self.average_moves != 0.0 {
+            cost += self.average_moves * average_moves(game);
+        }
+        if self.distance_to_apple != 0.0 {
+            cost += self.distance_to_apple * game.head().taxicab_distance_to(game.apple) as f32;
+        }
+        if self.reachable_area != 0.0 {
+            cost += self.reachable_area * reachable_area(game);
+        }
+        if self.tail_reachable != 0.0 {
+            cost += self.tail_reachable * can_reach_tail(game) as u8 as f32;
+        }
+        if self.compactness != 0.0 {
+            cost += self.compactness * compactness(game);
+        }
+        if self.wall_contact != 0.0 {
+            cost += self.wall_contact * wall_contact(game);
+        }
+        cost
+    }
+
+    /// Checks the weights average path prunes its search with aren't negative, since its lower
+    /// bound on the cost of a plan only holds if they aren't.
+    pub fn check_pruning(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("path_length", self.path_length),
+            ("average_moves", self.average_moves),
+        ] {
+            if value < 0.0 || value.is_nan() {
+                return Err(format!("average-path needs {} to be at least 0", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The lowest value the fractional features can add to a cost, for pruning searches.
+    pub fn fraction_lower_bound(&self) -> f32 {
+        [
+            self.reachable_area,
+            self.tail_reachable,
+            self.compactness,
+            self.wall_contact,
+        ]
+        .iter()
+        .map(|w| w.min(0.0))
+        .sum()
+    }
+}
+
+// Average number of moves required to reach open cells.
+pub fn average_moves(game: &SnakeGame) -> f32 {
+    let min_steps = steps_from_head(game);
+
+    let total = game
+        .non_body_cells()
+        .map(|cell| {
+            min_steps
+                .get(&cell)
+                .map(|steps| *steps as f32)
+                .unwrap_or(f32::INFINITY)
+        })
+        .sum::<f32>();
+    total / game.non_body_cells().count() as f32
+}
+
+#[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
+fn steps_from_head(game: &SnakeGame) -> HashMap<Cell, usize> {
+    crate::profile::Work::count(|w| w.bfs += 1);
+    let mut front = VecDeque::new();
+    front.push_back(game.head());
+
+    let mut min_steps: HashMap<Cell, usize> = HashMap::new();
+    while let Some(cell) = front.pop_front() {
+        let steps = *min_steps.get(&cell).unwrap_or(&0);
+        for neighbor in in_bounds_neighbors(game, cell) {
+            if game.is_blocked(neighbor) || min_steps.contains_key(&neighbor) {
+                continue;
+            }
+            front.push_back(neighbor);
+            min_steps.insert(neighbor, steps + 1);
+        }
+    }
+    min_steps
+}
+
+// Neighbors off the board are skipped rather than wrapped or clamped, so walls count as blocked.
+pub(crate) fn in_bounds_neighbors(game: &SnakeGame, cell: Cell) -> impl Iterator<Item = Cell> + '_ {
+    Heading::iter().filter_map(move |heading| game.cell_delta(cell, heading))
+}
+
+// Fraction of the non-body cells the head can reach.
+fn reachable_area(game: &SnakeGame) -> f32 {
+    let reached = steps_from_head(game).len();
+    reached as f32 / game.non_body_cells().count().max(1) as f32
+}
+
+// Whether the head can reach a cell next to the tail, which guarantees an escape route.
+pub fn can_reach_tail(game: &SnakeGame) -> bool {
+    let tail = game.body[0];
+    if game.body.len() == 1 || in_bounds_neighbors(game, game.head()).any(|c| c == tail) {
+        return true;
+    }
+    let reached = steps_from_head(game);
+    in_bounds_neighbors(game, tail).any(|c| reached.contains_key(&c))
+}
+
+// Fraction of body cell sides that touch another body cell.
+fn compactness(game: &SnakeGame) -> f32 {
+    let body = game.body.iter().copied().collect::<HashSet<_>>();
+    let touching = game
+        .body
+        .iter()
+        .flat_map(|cell| in_bounds_neighbors(game, *cell))
+        .filter(|cell| body.contains(cell))
+        .count();
+    touching as f32 / (4 * game.body.len()) as f32
+}
+
+// Fraction of body cells on the edge of the board.
+fn wall_contact(game: &SnakeGame) -> f32 {
+    let on_wall = game
+        .body
+        .iter()
+        .filter(|cell| in_bounds_neighbors(game, **cell).count() < 4)
+        .count();
+    on_wall as f32 / game.body.len() as f32
+}
+
+/// Weights for every search agent, stored as a `config` file with one section per agent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeuristicConfig {
+    pub average_path: Weights,
+    pub tree_search: Weights,
+}
+
+impl Default for HeuristicConfig {
+    fn default() -> Self {
+        HeuristicConfig {
+            average_path: Weights::average_path(),
+            tree_search: Weights::tree_search(),
+        }
+    }
+}
+
+impl HeuristicConfig {
+    pub const DEFAULT_PATH: &'static str = "heuristics.toml";
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut contents = String::new();
+        for (section, weights) in [
+            ("average_path", self.average_path),
+            ("tree_search", self.tree_search),
+        ] {
+            contents.push_str(&format!("[{}]\n", section));
+            for (name, value) in Weights::NAMES.iter().zip(weights.to_array()) {
+                contents.push_str(&format!("{} = {:?}\n", name, value));
+            }
+            contents.push('\n');
+        }
+        std::fs::write(path, contents)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        HeuristicConfig::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut config = HeuristicConfig::default();
+        for entry in super::config::parse(text)? {
+            let (weights, prunes) = match entry.section.as_deref() {
+                Some("average_path") => (&mut config.average_path, true),
+                Some("tree_search") => (&mut config.tree_search, false),
+                Some(_) => return Err(entry.error("unknown section")),
+                None => return Err(entry.error("weight outside of a section")),
+            };
+            if !weights.set(&entry.key, entry.number()?) {
+                return Err(entry.error("unknown weight"));
+            }
+            if prunes {
+                weights.check_pruning().map_err(|e| entry.error(e))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Loads the config at `path` if there is one, falling back to the built-in weights.
+    pub fn load_or_default(path: impl AsRef<Path>) -> io::Result<Self> {
+        match HeuristicConfig::load(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HeuristicConfig::default()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_and_loads() {
+        let config = HeuristicConfig {
+            tree_search: Weights {
+                wall_contact: -0.25,
+                ..Weights::tree_search()
+            },
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("snake-heuristics-{}", std::process::id()));
+        config.save(&path).unwrap();
+        let loaded = HeuristicConfig::load(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, config);
+
+        let err = HeuristicConfig::parse("[tree_search]\npath_length = 1\nspeed = 2").unwrap_err();
+        assert_eq!(err, "line 3: unknown weight");
+        assert!(HeuristicConfig::parse("path_length = 1").is_err());
+    }
+
+    #[test]
+    fn average_path_weights_it_prunes_with_arent_negative() {
+        let err = HeuristicConfig::parse("[average_path]\ncompactness = -1\naverage_moves = -0.5")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "line 3: average-path needs average_moves to be at least 0"
+        );
+        assert!(HeuristicConfig::parse("[tree_search]\npath_length = -1").is_ok());
+    }
+}
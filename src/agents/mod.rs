@@ -2,14 +2,103 @@ use crate::gameplay::*;
 use rand::prelude::*;
 
 pub mod average_path;
+pub mod compare;
+pub mod config;
 pub mod greedy;
-pub mod tree_search;
-
+pub mod heuristic;
+pub mod neural;
+pub mod q_learning;
+pub mod registry;
 pub mod simple_path;
-pub use simple_path::*;
+pub mod sweep;
+pub mod tree_search;
+pub mod tuning;
 
 pub trait Agent {
     fn action(&mut self, game: &SnakeGame) -> Action;
+
+    /// Actions planned to follow the last one returned by `action`, for agents that plan ahead.
+    fn plan(&self) -> Option<Vec<Action>> {
+        None
+    }
+
+    /// Drops any plan, for when the game moved on without following it.
+    fn reset(&mut self) {}
+}
+
+/// Actions in the order the learned agents number them, in Q-tables and network outputs alike.
+pub(crate) const ACTIONS: [Action; 3] = [Action::GoStraight, Action::TurnLeft, Action::TurnRight];
+
+/// A heading as a number from 0 to 3, for the learned agents' observations.
+pub(crate) fn heading_index(heading: Heading) -> usize {
+    match heading {
+        Heading::North => 0,
+        Heading::South => 1,
+        Heading::East => 2,
+        Heading::West => 3,
+    }
+}
+
+/// The first action that doesn't die on the next move, for searches that found no plan. Going
+/// straight when every action dies.
+pub(crate) fn surviving_action(game: &SnakeGame) -> Action {
+    Action::iter()
+        .find(|&action| !matches!(game.do_many([action]), Err(Terminal::Died)))
+        .unwrap_or(Action::GoStraight)
+}
+
+/// Agents that can play any position without files and repeat their moves, by the names the
+/// command line uses. `simple-path` is left out since it only handles the boards and positions
+/// its path covers, and `random` since its moves aren't seeded.
+pub const PLAYABLE: [&str; 3] = ["greedy", "average-path", "tree-search"];
+
+/// Plays `game` to the end without drawing it, or until `max_moves_without_apple` moves pass
+/// without eating, which returns `None` so agents stuck in a loop still finish.
+pub fn play_out(
+    agent: &mut dyn Agent,
+    game: &mut SnakeGame,
+    max_moves_without_apple: usize,
+) -> Option<Terminal> {
+    let mut moves_since_apple = 0;
+    loop {
+        let score = game.score;
+        if let Some(terminal) = game.do_action(agent.action(game)) {
+            return Some(terminal);
+        }
+        if game.score > score {
+            moves_since_apple = 0;
+        } else {
+            moves_since_apple += 1;
+        }
+        if moves_since_apple >= max_moves_without_apple {
+            return None;
+        }
+    }
+}
+
+/// `f` applied to every item, spread over up to `threads` scoped threads, in the items' order.
+pub(crate) fn par_map<T: Sync, R: Send>(
+    items: &[T],
+    threads: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let chunk = items.len().div_ceil(threads.max(1)).max(1);
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles = items
+            .chunks(chunk)
+            .map(|items| scope.spawn(move || items.iter().map(f).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("game thread panicked"))
+            .collect()
+    })
+}
+
+/// The agent called `name` with its default parameters, if it needs no files to play.
+pub fn by_name(name: &str, weights: &heuristic::HeuristicConfig) -> Option<Box<dyn Agent>> {
+    registry::AgentConfig::named(name).build(weights).ok()
 }
 
 #[derive(Default)]
@@ -19,7 +108,27 @@ impl Agent for Random {
     fn action(&mut self, _: &SnakeGame) -> Action {
         [Action::TurnLeft, Action::TurnRight, Action::GoStraight]
             .choose(&mut rand::thread_rng())
+            .copied()
             .unwrap()
-            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn searches_without_a_plan_still_survive_the_next_move() {
+        // Going straight or right hits the wall. Turning left is the only move that doesn't die
+        // at once, into a pocket with no way out, so neither search finds a plan.
+        let board = "0/0\n######\n#<++O#\n# ++ #\n######\nbody: 2,0 2,1 1,1 1,0 0,0";
+        let game = board.parse::<SnakeGame>().unwrap();
+        let agents: [Box<dyn Agent>; 2] = [
+            Box::new(average_path::AveragePath::default()),
+            Box::new(tree_search::TreeSearch::default()),
+        ];
+        for mut agent in agents {
+            assert_eq!(agent.action(&game), Action::TurnLeft);
+        }
     }
 }
@@ -0,0 +1,341 @@
+use super::{heading_index, par_map, play_out, Agent, ACTIONS};
+use crate::gameplay::*;
+
+use rand::{prelude::*, rngs::SmallRng};
+use std::{io, path::Path};
+
+pub const INPUTS: usize = 12;
+pub const OUTPUTS: usize = ACTIONS.len();
+
+/// Feed-forward network with tanh hidden layers, picking the action with the largest output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Genome {
+    shape: Vec<usize>,
+    weights: Vec<f32>,
+}
+
+impl Genome {
+    pub fn random(hidden: &[usize], rng: &mut impl Rng) -> Genome {
+        let shape = [INPUTS]
+            .into_iter()
+            .chain(hidden.iter().copied())
+            .chain([OUTPUTS])
+            .collect::<Vec<_>>();
+        let weights = (0..weight_count(&shape))
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
+        Genome { shape, weights }
+    }
+
+    pub fn outputs(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut values = inputs.to_vec();
+        let mut weights = self.weights.iter();
+        let layers = self.shape.len() - 1;
+
+        for (layer, window) in self.shape.windows(2).enumerate() {
+            let (from, to) = (window[0], window[1]);
+            values = (0..to)
+                .map(|_| {
+                    let bias = *weights.next().unwrap();
+                    let sum = values
+                        .iter()
+                        .zip(weights.by_ref().take(from))
+                        .map(|(v, w)| v * w)
+                        .sum::<f32>();
+                    if layer + 1 == layers {
+                        sum + bias
+                    } else {
+                        (sum + bias).tanh()
+                    }
+                })
+                .collect();
+        }
+        values
+    }
+
+    fn crossover(&self, other: &Genome, rng: &mut impl Rng) -> Genome {
+        let weights = self
+            .weights
+            .iter()
+            .zip(&other.weights)
+            .map(|(&a, &b)| if rng.gen() { a } else { b })
+            .collect();
+        Genome {
+            shape: self.shape.clone(),
+            weights,
+        }
+    }
+
+    fn mutate(&mut self, rate: f32, strength: f32, rng: &mut impl Rng) {
+        for weight in &mut self.weights {
+            if rng.gen::<f32>() < rate {
+                *weight += gaussian(rng) * strength;
+            }
+        }
+    }
+
+    /// Writes the layer sizes on the first line and the flattened weights on the second.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let join = |values: Vec<String>| values.join(" ");
+        let contents = format!(
+            "{}\n{}\n",
+            join(self.shape.iter().map(|s| s.to_string()).collect()),
+            join(self.weights.iter().map(|w| w.to_string()).collect()),
+        );
+        std::fs::write(path, contents)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Genome> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let contents = std::fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        let shape = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| invalid(format!("shape: {}", e)))?;
+        let weights = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|w| w.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| invalid(format!("weights: {}", e)))?;
+
+        if shape.len() < 2 || shape[0] != INPUTS || shape[shape.len() - 1] != OUTPUTS {
+            return Err(invalid(format!("unsupported shape {:?}", shape)));
+        }
+        if weights.len() != weight_count(&shape) {
+            return Err(invalid(format!(
+                "expected {} weights, got {}",
+                weight_count(&shape),
+                weights.len()
+            )));
+        }
+        Ok(Genome { shape, weights })
+    }
+}
+
+fn weight_count(shape: &[usize]) -> usize {
+    shape.windows(2).map(|w| (w[0] + 1) * w[1]).sum()
+}
+
+pub(crate) fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u1 = rng.gen::<f32>().max(f32::MIN_POSITIVE);
+    let u2 = rng.gen::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+pub struct Neural {
+    pub genome: Genome,
+}
+
+impl Agent for Neural {
+    fn action(&mut self, game: &SnakeGame) -> Action {
+        let outputs = self.genome.outputs(&observe(game));
+        let best = outputs
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap();
+        ACTIONS[best]
+    }
+}
+
+// For each relative direction: whether the next cell is deadly and how far the nearest obstacle
+// is; then the apple offset along and across the heading, then the heading one-hot.
+pub fn observe(game: &SnakeGame) -> [f32; INPUTS] {
+    let head = game.head();
+    let size = game.width.max(game.height) as f32;
+    let mut inputs = [0.0; INPUTS];
+
+    for (i, action) in ACTIONS.iter().enumerate() {
+        let heading = game.heading.after(*action);
+        let mut cell = head;
+        let mut distance = 0;
+        while let Some(next) = game.cell_delta(cell, heading) {
+            if game.is_blocked(next) {
+                break;
+            }
+            cell = next;
+            distance += 1;
+        }
+        inputs[i] = (distance == 0) as u8 as f32;
+        inputs[3 + i] = distance as f32 / size;
+    }
+
+    let dx = game.apple.0 as f32 - head.0 as f32;
+    let dy = game.apple.1 as f32 - head.1 as f32;
+    let (forward, right) = match game.heading {
+        Heading::North => (-dy, dx),
+        Heading::South => (dy, -dx),
+        Heading::East => (dx, dy),
+        Heading::West => (-dx, -dy),
+    };
+    inputs[6] = forward / size;
+    inputs[7] = right / size;
+
+    inputs[8 + heading_index(game.heading)] = 1.0;
+
+    inputs
+}
+
+#[derive(Debug, Clone)]
+pub struct Evolution {
+    pub hidden: Vec<usize>,
+    pub population: usize,
+    pub generations: usize,
+    pub elites: usize,
+    pub tournament: usize,
+    pub mutation_rate: f32,
+    pub mutation_strength: f32,
+    pub games: usize,
+    pub width: Coord,
+    pub height: Coord,
+    pub seed: u64,
+    pub max_moves_without_apple: usize,
+    pub threads: usize,
+}
+
+impl Default for Evolution {
+    fn default() -> Self {
+        Evolution {
+            hidden: vec![16],
+            population: 200,
+            generations: 100,
+            elites: 10,
+            tournament: 5,
+            mutation_rate: 0.1,
+            mutation_strength: 0.3,
+            games: 5,
+            width: 10,
+            height: 10,
+            seed: 0,
+            max_moves_without_apple: 200,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Evolution {
+    /// Runs the genetic algorithm, calling `on_generation` with each generation's best fitness,
+    /// and returns the fittest genome of the final generation.
+    pub fn run(&self, mut on_generation: impl FnMut(usize, f32)) -> Result<Genome, String> {
+        if self.population == 0 {
+            return Err("the population needs at least one genome".to_string());
+        }
+
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        let mut population = (0..self.population)
+            .map(|_| Genome::random(&self.hidden, &mut rng))
+            .collect::<Vec<_>>();
+
+        let mut generation = 0;
+        loop {
+            let fitness = self.evaluate(&population, generation);
+            let mut ranked = population.into_iter().zip(fitness).collect::<Vec<_>>();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+            on_generation(generation, ranked[0].1);
+
+            generation += 1;
+            if generation >= self.generations {
+                return Ok(ranked.swap_remove(0).0);
+            }
+
+            population = ranked
+                .iter()
+                .take(self.elites)
+                .map(|(genome, _)| genome.clone())
+                .collect();
+            while population.len() < self.population {
+                let a = self.select(&ranked, &mut rng);
+                let b = self.select(&ranked, &mut rng);
+                let mut child = a.crossover(b, &mut rng);
+                child.mutate(self.mutation_rate, self.mutation_strength, &mut rng);
+                population.push(child);
+            }
+        }
+    }
+
+    fn select<'r>(&self, ranked: &'r [(Genome, f32)], rng: &mut impl Rng) -> &'r Genome {
+        // Ranked is sorted best first, so the smallest sampled index wins the tournament.
+        let winner = (0..self.tournament.max(1))
+            .map(|_| rng.gen_range(0..ranked.len()))
+            .min()
+            .unwrap();
+        &ranked[winner].0
+    }
+
+    fn evaluate(&self, population: &[Genome], generation: usize) -> Vec<f32> {
+        // Every genome of a generation plays the same boards so their fitness is comparable.
+        let seed = self.seed.wrapping_add((generation * self.games) as u64);
+        par_map(population, self.threads, |genome| {
+            self.fitness(genome, seed)
+        })
+    }
+
+    fn fitness(&self, genome: &Genome, seed: u64) -> f32 {
+        let mut agent = Neural {
+            genome: genome.clone(),
+        };
+        let cells = self.width as f32 * self.height as f32;
+
+        (0..self.games)
+            .map(|game| {
+                let mut game =
+                    SnakeGame::seeded(self.width, self.height, seed.wrapping_add(game as u64));
+                play_out(&mut agent, &mut game, self.max_moves_without_apple);
+                game.score as f32 + (game.moves as f32 / cells).min(1.0)
+            })
+            .sum::<f32>()
+            / self.games.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_of_a_known_network() {
+        // Each unit's bias comes before its input weights. One hidden unit weighs the first two
+        // inputs, and the outputs are `1 + 2h`, `-1` and `h`.
+        let mut weights = vec![0.0; weight_count(&[INPUTS, 1, OUTPUTS])];
+        weights[1] = 0.5;
+        weights[2] = 0.25;
+        weights[INPUTS + 1..].copy_from_slice(&[1.0, 2.0, -1.0, 0.0, 0.0, 1.0]);
+        let genome = Genome {
+            shape: vec![INPUTS, 1, OUTPUTS],
+            weights,
+        };
+
+        let mut inputs = [0.0; INPUTS];
+        inputs[0] = 1.0;
+        inputs[1] = 2.0;
+        let hidden = 1.0f32.tanh();
+        assert_eq!(genome.outputs(&inputs), [1.0 + 2.0 * hidden, -1.0, hidden]);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let genome = Genome::random(&[5, 4], &mut SmallRng::seed_from_u64(3));
+        let path = std::env::temp_dir().join(format!("snake-genome-{}", std::process::id()));
+        genome.save(&path).unwrap();
+        let loaded = Genome::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, genome);
+    }
+
+    #[test]
+    fn empty_populations_are_an_error() {
+        let evolution = Evolution {
+            population: 0,
+            ..Default::default()
+        };
+        assert!(evolution.run(|_, _| {}).is_err());
+    }
+}
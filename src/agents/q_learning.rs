@@ -0,0 +1,340 @@
+use super::{heading_index, Agent, ACTIONS};
+use crate::gameplay::*;
+
+use rand::{prelude::*, rngs::SmallRng};
+use std::{io, path::Path};
+
+// 2^3 danger combinations * (4 apple headings + none) * 4 headings.
+const STATES: usize = 8 * 5 * 4;
+
+const APPLE_REWARD: f32 = 1.0;
+const DEATH_REWARD: f32 = -1.0;
+
+/// Tabular agent over a compact observation of the board, trained with Q-learning or SARSA.
+pub struct QLearning {
+    table: Vec<[f32; 3]>,
+}
+
+impl Default for QLearning {
+    fn default() -> Self {
+        QLearning {
+            table: vec![[0.0; 3]; STATES],
+        }
+    }
+}
+
+impl Agent for QLearning {
+    fn action(&mut self, game: &SnakeGame) -> Action {
+        ACTIONS[best_index(&self.table[Observation::of(game).index()])]
+    }
+}
+
+impl QLearning {
+    /// Trains in place, returning the score of every episode played.
+    pub fn train(&mut self, training: &Training) -> Vec<usize> {
+        let mut rng = SmallRng::seed_from_u64(training.seed);
+
+        (0..training.episodes)
+            .map(|episode| {
+                let epsilon = training.epsilon.at(episode);
+                let game = SnakeGame::seeded(
+                    training.width,
+                    training.height,
+                    training.seed.wrapping_add(episode as u64),
+                );
+                self.play_episode(game, training, epsilon, &mut rng)
+            })
+            .collect()
+    }
+
+    fn play_episode(
+        &mut self,
+        mut game: SnakeGame,
+        training: &Training,
+        epsilon: f32,
+        rng: &mut SmallRng,
+    ) -> usize {
+        let mut observation = Observation::of(&game).index();
+        let mut action = self.choose(observation, epsilon, rng);
+        let mut moves_since_apple = 0;
+
+        loop {
+            let score = game.score;
+            let (reward, done) = match game.do_action(ACTIONS[action]) {
+                Some(Terminal::Died) => (DEATH_REWARD, true),
+                Some(Terminal::Won) => (APPLE_REWARD, true),
+                None if game.score > score => (APPLE_REWARD, false),
+                None => (0.0, false),
+            };
+
+            if done {
+                self.update(observation, action, reward, training.learning_rate);
+                return game.score;
+            }
+
+            let next_observation = Observation::of(&game).index();
+            let next_action = self.choose(next_observation, epsilon, rng);
+            let future = match training.method {
+                Method::QLearning => {
+                    self.table[next_observation][best_index(&self.table[next_observation])]
+                }
+                Method::Sarsa => self.table[next_observation][next_action],
+            };
+            self.update(
+                observation,
+                action,
+                reward + training.discount * future,
+                training.learning_rate,
+            );
+
+            if reward > 0.0 {
+                moves_since_apple = 0;
+            } else {
+                moves_since_apple += 1;
+            }
+            if moves_since_apple >= training.max_moves_without_apple {
+                return game.score;
+            }
+
+            observation = next_observation;
+            action = next_action;
+        }
+    }
+
+    fn choose(&self, observation: usize, epsilon: f32, rng: &mut SmallRng) -> usize {
+        if rng.gen::<f32>() < epsilon {
+            rng.gen_range(0..ACTIONS.len())
+        } else {
+            best_index(&self.table[observation])
+        }
+    }
+
+    fn update(&mut self, observation: usize, action: usize, target: f32, learning_rate: f32) {
+        let value = &mut self.table[observation][action];
+        *value += learning_rate * (target - *value);
+    }
+
+    /// Writes the table as one line of three action values per observation.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents = self
+            .table
+            .iter()
+            .map(|values| format!("{} {} {}\n", values[0], values[1], values[2]))
+            .collect::<String>();
+        std::fs::write(path, contents)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let contents = std::fs::read_to_string(path)?;
+        let table = contents
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let values = line
+                    .split_whitespace()
+                    .map(|v| v.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| invalid(format!("line {}: {}", i + 1, e)))?;
+                <[f32; 3]>::try_from(values).map_err(|v| {
+                    invalid(format!(
+                        "line {}: expected 3 values, got {}",
+                        i + 1,
+                        v.len()
+                    ))
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if table.len() != STATES {
+            return Err(invalid(format!(
+                "expected {} rows, got {}",
+                STATES,
+                table.len()
+            )));
+        }
+        Ok(QLearning { table })
+    }
+}
+
+fn best_index(values: &[f32; 3]) -> usize {
+    (0..values.len()).fold(0, |best, i| if values[i] > values[best] { i } else { best })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Observation {
+    danger: [bool; 3],
+    apple: Option<Heading>,
+    heading: Heading,
+}
+
+impl Observation {
+    fn of(game: &SnakeGame) -> Observation {
+        let head = game.head();
+        let danger =
+            ACTIONS.map(
+                |action| match game.cell_delta(head, game.heading.after(action)) {
+                    None => true,
+                    Some(cell) => game.is_blocked(cell),
+                },
+            );
+
+        Observation {
+            danger,
+            apple: head.heading_toward(game.apple),
+            heading: game.heading,
+        }
+    }
+
+    fn index(&self) -> usize {
+        let danger = self
+            .danger
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| (d as usize) << i)
+            .sum::<usize>();
+        let apple = self.apple.map(heading_index).unwrap_or(4);
+        (danger * 5 + apple) * 4 + heading_index(self.heading)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    QLearning,
+    Sarsa,
+}
+
+/// Exploration rate as a function of the episode number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Epsilon {
+    Constant(f32),
+    Linear {
+        start: f32,
+        end: f32,
+        episodes: usize,
+    },
+    Exponential {
+        start: f32,
+        end: f32,
+        decay: f32,
+    },
+}
+
+impl Epsilon {
+    pub fn at(&self, episode: usize) -> f32 {
+        match *self {
+            Epsilon::Constant(e) => e,
+            Epsilon::Linear {
+                start,
+                end,
+                episodes,
+            } => {
+                let progress = (episode as f32 / episodes.max(1) as f32).min(1.0);
+                start + (end - start) * progress
+            }
+            Epsilon::Exponential { start, end, decay } => {
+                end + (start - end) * decay.powi(episode as i32)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Training {
+    pub method: Method,
+    pub episodes: usize,
+    pub width: Coord,
+    pub height: Coord,
+    pub seed: u64,
+    pub learning_rate: f32,
+    pub discount: f32,
+    pub epsilon: Epsilon,
+    pub max_moves_without_apple: usize,
+}
+
+impl Default for Training {
+    fn default() -> Self {
+        Training {
+            method: Method::QLearning,
+            episodes: 10_000,
+            width: 10,
+            height: 10,
+            seed: 0,
+            learning_rate: 0.1,
+            discount: 0.9,
+            epsilon: Epsilon::Linear {
+                start: 1.0,
+                end: 0.01,
+                episodes: 8_000,
+            },
+            max_moves_without_apple: 200,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::play_out;
+
+    fn mean_score(agent: &mut QLearning) -> f32 {
+        let scores = (0..20).map(|seed| {
+            let mut game = SnakeGame::seeded(10, 10, 1000 + seed);
+            play_out(agent, &mut game, 200);
+            game.score
+        });
+        scores.sum::<usize>() as f32 / 20.0
+    }
+
+    #[test]
+    fn observations_index_every_state_once() {
+        let mut seen = vec![false; STATES];
+        for danger in 0..8 {
+            for apple in Heading::iter().map(Some).chain([None]) {
+                for heading in Heading::iter() {
+                    let observation = Observation {
+                        danger: [0, 1, 2].map(|i| danger >> i & 1 == 1),
+                        apple,
+                        heading,
+                    };
+                    let index = observation.index();
+                    assert!(!seen[index], "{:?} shares index {}", observation, index);
+                    seen[index] = true;
+                }
+            }
+        }
+        assert!(seen.into_iter().all(|s| s));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut agent = QLearning::default();
+        agent.train(&Training {
+            episodes: 50,
+            ..Default::default()
+        });
+        let path = std::env::temp_dir().join(format!("snake-q-table-{}", std::process::id()));
+        agent.save(&path).unwrap();
+        let loaded = QLearning::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.table, agent.table);
+    }
+
+    #[test]
+    fn training_improves_the_score() {
+        let mut agent = QLearning::default();
+        let untrained = mean_score(&mut agent);
+        agent.train(&Training {
+            episodes: 2_000,
+            epsilon: Epsilon::Linear {
+                start: 1.0,
+                end: 0.01,
+                episodes: 1_500,
+            },
+            ..Default::default()
+        });
+        let trained = mean_score(&mut agent);
+        assert!(trained > untrained + 1.0, "{} -> {}", untrained, trained);
+    }
+}
@@ -0,0 +1,425 @@
+use super::{
+    average_path::AveragePath,
+    config,
+    greedy::Greedy,
+    heuristic::{HeuristicConfig, Weights},
+    neural::{Genome, Neural},
+    q_learning::QLearning,
+    simple_path::SimplePath,
+    tree_search::TreeSearch,
+    Agent, Random,
+};
+
+use std::{fmt, io, path::Path, str::FromStr};
+
+/// An agent that can be picked by name, and the parameters it takes.
+pub struct Entry {
+    pub name: &'static str,
+    pub about: &'static str,
+    params: &'static [Param],
+    // Whether every heuristic weight is a parameter too, starting from the heuristics file.
+    weights: bool,
+    build: Build,
+}
+
+type Build = fn(&AgentConfig, &HeuristicConfig) -> Result<Box<dyn Agent>, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Param {
+    pub name: &'static str,
+    pub about: &'static str,
+}
+
+// Every heuristic weight, for agents that take them as parameters.
+const WEIGHTS: [Param; 8] = {
+    let mut params = [Param {
+        name: "",
+        about: "",
+    }; 8];
+    let mut i = 0;
+    while i < params.len() {
+        let (name, about) = Weights::ABOUT[i];
+        params[i] = Param { name, about };
+        i += 1;
+    }
+    params
+};
+
+/// Every agent, in the order the command line lists them.
+pub const AGENTS: [Entry; 7] = [
+    Entry {
+        name: "random",
+        about: "turns at random",
+        params: &[],
+        weights: false,
+        build: |_, _| Ok(Box::new(Random)),
+    },
+    Entry {
+        name: "greedy",
+        about: "heads straight for the apple",
+        params: &[],
+        weights: false,
+        build: |_, _| Ok(Box::new(Greedy)),
+    },
+    Entry {
+        name: "simple-path",
+        about: "follows a fixed cycle through every cell",
+        params: &[],
+        weights: false,
+        build: |_, _| Ok(Box::new(SimplePath)),
+    },
+    Entry {
+        name: "average-path",
+        about: "searches for the path to the apple that keeps the open cells closest",
+        params: &[Param {
+            name: "budget",
+            about: "partial plans searched per apple, unlimited if left out",
+        }],
+        weights: true,
+        build: |config, heuristics| {
+            let weights = config.weights(heuristics.average_path)?;
+            weights.check_pruning()?;
+            let agent = AveragePath::with_weights(weights);
+            Ok(Box::new(match config.get("budget")? {
+                Some(budget) => agent.with_budget(budget),
+                None => agent,
+            }))
+        },
+    },
+    Entry {
+        name: "tree-search",
+        about: "best-first search for the cheapest path to the apple",
+        params: &[],
+        weights: true,
+        build: |config, heuristics| {
+            Ok(Box::new(TreeSearch::with_weights(
+                config.weights(heuristics.tree_search)?,
+            )))
+        },
+    },
+    Entry {
+        name: "q",
+        about: "plays from a Q-learning or SARSA table",
+        params: &[Param {
+            name: "table",
+            about: "path to the table saved by train-q or train-sarsa",
+        }],
+        weights: false,
+        build: |config, _| {
+            let path = config.require::<String>("table")?;
+            let agent = QLearning::load(&path).map_err(|e| format!("{}: {}", path, e))?;
+            Ok(Box::new(agent))
+        },
+    },
+    Entry {
+        name: "neural",
+        about: "plays from an evolved network",
+        params: &[Param {
+            name: "genome",
+            about: "path to the genome saved by evolve",
+        }],
+        weights: false,
+        build: |config, _| {
+            let path = config.require::<String>("genome")?;
+            let genome = Genome::load(&path).map_err(|e| format!("{}: {}", path, e))?;
+            Ok(Box::new(Neural { genome }))
+        },
+    },
+];
+
+impl Entry {
+    pub fn find(name: &str) -> Option<&'static Entry> {
+        AGENTS.iter().find(|e| e.name == name)
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = &'static Param> {
+        let weights: &'static [Param] = if self.weights { &WEIGHTS } else { &[] };
+        self.params.iter().chain(weights)
+    }
+}
+
+/// An agent by name with values for any of its parameters, written `name` or
+/// `name:param=value,param=value`, e.g. `average-path:budget=500,wall_contact=0.5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentConfig {
+    pub agent: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl AgentConfig {
+    pub fn named(agent: &str) -> AgentConfig {
+        AgentConfig {
+            agent: agent.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// The config with `param` set to `value`, replacing any value it had.
+    pub fn with(mut self, param: &str, value: impl ToString) -> AgentConfig {
+        self.params.retain(|(p, _)| p != param);
+        self.params.push((param.to_string(), value.to_string()));
+        self
+    }
+
+    /// A config string, or a path to a `.toml` file with the same settings:
+    ///
+    /// ```toml
+    /// agent = "average-path"
+    /// budget = 500
+    /// wall_contact = 0.5
+    /// ```
+    pub fn from_arg(arg: &str) -> Result<AgentConfig, String> {
+        if arg.ends_with(".toml") {
+            AgentConfig::load(arg).map_err(|e| format!("{}: {}", arg, e))
+        } else {
+            arg.parse()
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<AgentConfig> {
+        AgentConfig::parse_file(&std::fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn parse_file(text: &str) -> Result<AgentConfig, String> {
+        let mut agent = None;
+        let mut params = Vec::new();
+        for entry in config::parse(text)? {
+            if entry.section.is_some() {
+                return Err(entry.error("agent files don't have sections"));
+            }
+            let value = entry.text()?.to_string();
+            match entry.key.as_str() {
+                "agent" => agent = Some(value),
+                _ => params.push((entry.key, value)),
+            }
+        }
+
+        let agent = agent.ok_or("missing `agent = \"<name>\"`")?;
+        Ok(AgentConfig { agent, params })
+    }
+
+    pub fn build(&self, heuristics: &HeuristicConfig) -> Result<Box<dyn Agent>, String> {
+        let entry =
+            Entry::find(&self.agent).ok_or_else(|| format!("unknown agent {:?}", self.agent))?;
+        if let Some((param, _)) = self
+            .params
+            .iter()
+            .find(|(p, _)| !entry.params().any(|known| known.name == p))
+        {
+            return Err(format!("{} has no parameter {:?}", entry.name, param));
+        }
+        (entry.build)(self, heuristics)
+    }
+
+    pub fn get<T: FromStr>(&self, param: &str) -> Result<Option<T>, String> {
+        self.params
+            .iter()
+            .rev()
+            .find(|(p, _)| p == param)
+            .map(|(_, value)| {
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid value {:?} for {}", value, param))
+            })
+            .transpose()
+    }
+
+    fn require<T: FromStr>(&self, param: &str) -> Result<T, String> {
+        self.get(param)?
+            .ok_or_else(|| format!("{} needs `{}=...`", self.agent, param))
+    }
+
+    // `defaults` with any weights the config sets.
+    fn weights(&self, defaults: Weights) -> Result<Weights, String> {
+        let mut weights = defaults;
+        for name in Weights::NAMES {
+            if let Some(value) = self.get(name)? {
+                weights.set(name, value);
+            }
+        }
+        Ok(weights)
+    }
+}
+
+impl FromStr for AgentConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (agent, params) = s.split_once(':').unwrap_or((s, ""));
+        let params = params
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                p.split_once('=')
+                    .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                    .ok_or_else(|| format!("expected `param=value`, got {:?}", p))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(AgentConfig {
+            agent: agent.trim().to_string(),
+            params,
+        })
+    }
+}
+
+/// The inverse of `from_str`.
+impl fmt::Display for AgentConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.agent)?;
+        for (i, (param, value)) in self.params.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { ':' } else { ',' }, param, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::SnakeGame;
+
+    #[test]
+    fn config_strings_round_trip() {
+        for s in ["greedy", "average-path:budget=500,wall_contact=0.5"] {
+            let config = s.parse::<AgentConfig>().unwrap();
+            assert_eq!(config.to_string(), s);
+        }
+        let config = "tree-search: path_length = 2 "
+            .parse::<AgentConfig>()
+            .unwrap();
+        assert_eq!(
+            config,
+            AgentConfig::named("tree-search").with("path_length", 2)
+        );
+        assert!("tree-search:path_length".parse::<AgentConfig>().is_err());
+    }
+
+    #[test]
+    fn builds_every_agent_that_needs_no_files() {
+        let heuristics = HeuristicConfig::default();
+        let game = SnakeGame::seeded(6, 6, 3);
+        for entry in &AGENTS {
+            let agent = AgentConfig::named(entry.name).build(&heuristics);
+            match entry.name {
+                "q" | "neural" => assert!(agent.err().unwrap().contains("needs")),
+                _ => {
+                    agent.unwrap().action(&game);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn agents_play_boards_wider_than_a_byte() {
+        let heuristics = HeuristicConfig::default();
+        // Without a budget, average-path searches every way along the board to a far apple.
+        let agents = [
+            "random",
+            "greedy",
+            "simple-path",
+            "average-path:budget=500",
+            "tree-search",
+        ];
+        for config in agents {
+            let config = config.parse::<AgentConfig>().unwrap();
+            let mut agent = config.build(&heuristics).unwrap();
+            let mut game = SnakeGame::seeded(260, 4, 1);
+            for _ in 0..20 {
+                if game.do_action(agent.action(&game)).is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tree_search_sees_the_east_wall_past_a_byte() {
+        // The east-wall-pocket fixture against the east wall of a board 300 wide, where a room
+        // check running past the wall would find the whole width of room beyond it.
+        let pad = " ".repeat(294);
+        let mut board = format!("7/0\n{}\n", "#".repeat(302));
+        for row in ["+++++O", "    + ", "    +>", "      "] {
+            board += &format!("#{}{}#\n", pad, row);
+        }
+        board += &"#".repeat(302);
+        let mut game: SnakeGame = board.parse().unwrap();
+        assert_eq!(game.width, 300);
+
+        let mut agent = AgentConfig::named("tree-search")
+            .build(&HeuristicConfig::default())
+            .unwrap();
+        for _ in 0..20 {
+            assert_eq!(game.do_action(agent.action(&game)), None);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_params() {
+        let heuristics = HeuristicConfig::default();
+        let build = |s: &str| s.parse::<AgentConfig>().unwrap().build(&heuristics);
+        assert!(build("average-path:budget=50,compactness=-0.5").is_ok());
+        assert!(build("greedy:budget=50")
+            .err()
+            .unwrap()
+            .contains("no parameter"));
+        assert!(build("tree-search:budget=50").is_err());
+        assert_eq!(
+            build("average-path:path_length=-1").err().unwrap(),
+            "average-path needs path_length to be at least 0"
+        );
+        assert!(build("tree-search:path_length=-1").is_ok());
+        assert!(build("average-path:budget=lots")
+            .err()
+            .unwrap()
+            .contains("invalid"));
+        assert!(build("nobody").err().unwrap().contains("unknown agent"));
+        assert!(build("q:table=/nonexistent").is_err());
+    }
+
+    #[test]
+    fn weights_start_from_the_heuristics() {
+        let config = AgentConfig::named("tree-search").with("path_length", 3.5);
+        let weights = config.weights(Weights::tree_search()).unwrap();
+        assert_eq!(
+            weights,
+            Weights {
+                path_length: 3.5,
+                ..Weights::tree_search()
+            }
+        );
+    }
+
+    #[test]
+    fn loads_toml() {
+        let path = std::env::temp_dir().join(format!("snake-agent-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "# A tuned agent.\nagent = \"average-path\"\nbudget = 500 # per apple\n\n\
+             wall_contact = 0.5\n",
+        )
+        .unwrap();
+        let config = AgentConfig::from_arg(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(
+            config,
+            AgentConfig::named("average-path")
+                .with("budget", 500)
+                .with("wall_contact", 0.5)
+        );
+    }
+
+    #[test]
+    fn agent_files_keep_hashes_in_strings() {
+        let config = AgentConfig::parse_file("agent = \"q\"\ntable = \"tables/#3.q\" # tuned\n");
+        assert_eq!(
+            config,
+            Ok(AgentConfig::named("q").with("table", "tables/#3.q"))
+        );
+        let err = AgentConfig::parse_file("agent = \"q\"\n[q]\ntable = \"a.q\"").unwrap_err();
+        assert!(err.starts_with("line 3:"), "{}", err);
+        assert!(AgentConfig::parse_file("agent = [\"q\", \"neural\"]").is_err());
+        assert!(AgentConfig::parse_file("budget = 5").is_err());
+    }
+}
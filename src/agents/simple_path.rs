@@ -9,7 +9,7 @@ impl Agent for SimplePath {
         use Action::*;
         use Heading::*;
 
-        assert!(game.height % 2 == 0);
+        assert!(game.height.is_multiple_of(2));
 
         match (game.head(), game.heading) {
             (Cell(0, 0), h) => h.turn_towards(East).unwrap_or(TurnLeft),
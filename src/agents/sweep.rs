@@ -0,0 +1,431 @@
+use super::{config, heuristic::HeuristicConfig, par_map, play_out, registry::AgentConfig};
+use crate::gameplay::*;
+
+use rand::{prelude::*, rngs::SmallRng};
+use std::{fmt::Write as _, io, path::Path};
+
+/// Seeded games for every combination of an agent's parameters and board sizes, read from a
+/// TOML spec:
+///
+/// ```toml
+/// agent = "average-path"
+/// sizes = ["6x6", "8x8"]
+/// games = 8
+/// budget = [500, 2000]
+/// wall_contact = "0.0..1.0"
+/// samples = 10
+/// ```
+///
+/// A list of values is searched as a grid. A `lo..hi` range is sampled uniformly, as whole numbers
+/// if both ends are written as whole numbers, and needs `samples` to say how many random
+/// combinations to try. With `samples`, lists are sampled from too. `seed` sets the boards and
+/// samples, and `max_moves_without_apple` ends games an agent is looping in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sweep {
+    pub agent: String,
+    pub sizes: Vec<(Coord, Coord)>,
+    pub games: usize,
+    pub seed: u64,
+    pub samples: Option<usize>,
+    pub params: Vec<(String, Values)>,
+    pub max_moves_without_apple: usize,
+    pub threads: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Values {
+    List(Vec<String>),
+    Range(f64, f64),
+    // A range written with whole numbers, for parameters like `budget` that only take them.
+    IntRange(i64, i64),
+}
+
+/// One agent configuration on one board size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trial {
+    pub config: AgentConfig,
+    pub width: Coord,
+    pub height: Coord,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub trial: Trial,
+    pub mean_score: f32,
+    pub mean_moves: f32,
+    pub died: usize,
+    pub won: usize,
+    // Games ended for going `max_moves_without_apple` moves without eating.
+    pub stalled: usize,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep {
+            agent: String::new(),
+            sizes: vec![(10, 10)],
+            games: 8,
+            seed: 0,
+            samples: None,
+            params: Vec::new(),
+            max_moves_without_apple: 500,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Sweep {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Sweep> {
+        Sweep::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Sweep, String> {
+        let mut sweep = Sweep::default();
+        for entry in config::parse(text)? {
+            if entry.section.is_some() {
+                return Err(entry.error("sweep specs don't have sections"));
+            }
+            match entry.key.as_str() {
+                "agent" => sweep.agent = entry.text()?.to_string(),
+                "sizes" => {
+                    sweep.sizes = entry
+                        .list()
+                        .iter()
+                        .map(|size| parse_size(size).map_err(|e| entry.error(e)))
+                        .collect::<Result<_, _>>()?
+                }
+                "games" => sweep.games = entry.number()?,
+                "seed" => sweep.seed = entry.number()?,
+                "samples" => sweep.samples = Some(entry.number()?),
+                "max_moves_without_apple" => sweep.max_moves_without_apple = entry.number()?,
+                "threads" => sweep.threads = entry.number()?,
+                param => {
+                    let range = match &entry.value {
+                        config::Value::One(value) => value.split_once(".."),
+                        config::Value::List(_) => None,
+                    };
+                    let values = match range {
+                        Some((lo, hi)) => range_values(lo.trim(), hi.trim())
+                            .map_err(|e| entry.error(format!("{}: {}", param, e)))?,
+                        None => Values::List(entry.list()),
+                    };
+                    sweep.params.push((param.to_string(), values));
+                }
+            }
+        }
+
+        if sweep.agent.is_empty() {
+            return Err("missing `agent`".to_string());
+        }
+        if sweep.sizes.is_empty() {
+            return Err("`sizes` is empty".to_string());
+        }
+        let ranged = sweep
+            .params
+            .iter()
+            .any(|(_, v)| !matches!(v, Values::List(..)));
+        if ranged && sweep.samples.is_none() {
+            return Err("ranges need `samples`".to_string());
+        }
+        Ok(sweep)
+    }
+
+    /// Every combination to play, in the order the spec lists parameters and sizes.
+    pub fn trials(&self) -> Vec<Trial> {
+        let configs = match self.samples {
+            Some(samples) => self.sample(samples),
+            None => self.grid(),
+        };
+        configs
+            .into_iter()
+            .flat_map(|config| {
+                self.sizes.iter().map(move |&(width, height)| Trial {
+                    config: config.clone(),
+                    width,
+                    height,
+                })
+            })
+            .collect()
+    }
+
+    fn grid(&self) -> Vec<AgentConfig> {
+        let mut configs = vec![AgentConfig::named(&self.agent)];
+        for (param, values) in &self.params {
+            let Values::List(values) = values else {
+                unreachable!("`parse` only allows ranges with `samples`")
+            };
+            configs = configs
+                .into_iter()
+                .flat_map(|config| values.iter().map(move |v| config.clone().with(param, v)))
+                .collect();
+        }
+        configs
+    }
+
+    fn sample(&self, samples: usize) -> Vec<AgentConfig> {
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        (0..samples)
+            .map(|_| {
+                self.params.iter().fold(
+                    AgentConfig::named(&self.agent),
+                    |config, (param, values)| {
+                        let value = match *values {
+                            Values::List(ref values) => values.choose(&mut rng).unwrap().clone(),
+                            Values::Range(lo, hi) => format!("{:.4}", rng.gen_range(lo..=hi)),
+                            Values::IntRange(lo, hi) => rng.gen_range(lo..=hi).to_string(),
+                        };
+                        config.with(param, value)
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Plays every trial, spread over `threads`, and ranks them by mean score, best first.
+    pub fn run(&self, heuristics: &HeuristicConfig) -> Result<Vec<Outcome>, String> {
+        let trials = self.trials();
+        // Fail on bad parameters before spending time on games.
+        for trial in &trials {
+            trial.config.build(heuristics)?;
+        }
+
+        let mut outcomes = par_map(&trials, self.threads, |t| self.play(t, heuristics));
+        outcomes.sort_by(|a, b| b.mean_score.total_cmp(&a.mean_score));
+        Ok(outcomes)
+    }
+
+    fn play(&self, trial: &Trial, heuristics: &HeuristicConfig) -> Outcome {
+        let mut outcome = Outcome {
+            trial: trial.clone(),
+            mean_score: 0.0,
+            mean_moves: 0.0,
+            died: 0,
+            won: 0,
+            stalled: 0,
+        };
+        for game in 0..self.games {
+            let mut agent = trial.config.build(heuristics).unwrap();
+            let seed = self.seed.wrapping_add(game as u64);
+            let mut game = SnakeGame::seeded(trial.width, trial.height, seed);
+            match play_out(agent.as_mut(), &mut game, self.max_moves_without_apple) {
+                Some(Terminal::Died) => outcome.died += 1,
+                Some(Terminal::Won) => outcome.won += 1,
+                None => outcome.stalled += 1,
+            }
+            outcome.mean_score += game.score as f32;
+            outcome.mean_moves += game.moves as f32;
+        }
+        outcome.mean_score /= self.games.max(1) as f32;
+        outcome.mean_moves /= self.games.max(1) as f32;
+        outcome
+    }
+
+    // The swept parameter names, for table and CSV columns.
+    fn columns(&self) -> Vec<&str> {
+        self.params.iter().map(|(p, _)| p.as_str()).collect()
+    }
+
+    /// Ranked outcomes as an aligned table, one row per trial.
+    pub fn table(&self, outcomes: &[Outcome]) -> String {
+        let mut header = vec!["rank", "size"];
+        header.extend(self.columns());
+        header.extend(["score", "moves", "died", "won", "stalled"]);
+        let rows = self.rows(outcomes, |score| format!("{:.2}", score));
+
+        let widths = header
+            .iter()
+            .enumerate()
+            .map(|(i, h)| rows.iter().map(|r| r[i].len()).fold(h.len(), usize::max))
+            .collect::<Vec<_>>();
+        let mut table = String::new();
+        for row in std::iter::once(header.iter().map(|h| h.to_string()).collect()).chain(rows) {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            writeln!(table, "{}", cells.join("  ").trim_end()).unwrap();
+        }
+        table
+    }
+
+    /// Ranked outcomes as CSV, with a header row.
+    pub fn csv(&self, outcomes: &[Outcome]) -> String {
+        let mut header = vec!["rank", "width", "height"];
+        header.extend(self.columns());
+        header.extend(["mean_score", "mean_moves", "died", "won", "stalled"]);
+        let mut csv = header.join(",") + "\n";
+        for row in self.rows(outcomes, |score| score.to_string()) {
+            // Splits the `size` column back into width and height.
+            let (width, height) = row[1].split_once('x').unwrap();
+            let mut fields = vec![row[0].clone(), width.to_string(), height.to_string()];
+            fields.extend(row[2..].iter().cloned());
+            writeln!(csv, "{}", fields.join(",")).unwrap();
+        }
+        csv
+    }
+
+    fn rows(&self, outcomes: &[Outcome], number: impl Fn(f32) -> String) -> Vec<Vec<String>> {
+        outcomes
+            .iter()
+            .enumerate()
+            .map(|(rank, outcome)| {
+                let trial = &outcome.trial;
+                let mut row = vec![
+                    (rank + 1).to_string(),
+                    format!("{}x{}", trial.width, trial.height),
+                ];
+                for param in self.columns() {
+                    row.push(
+                        trial
+                            .config
+                            .get::<String>(param)
+                            .unwrap()
+                            .unwrap_or_default(),
+                    );
+                }
+                row.extend([
+                    number(outcome.mean_score),
+                    number(outcome.mean_moves),
+                    outcome.died.to_string(),
+                    outcome.won.to_string(),
+                    outcome.stalled.to_string(),
+                ]);
+                row
+            })
+            .collect()
+    }
+}
+
+// Whole numbers if both ends are. Sampling needs a value to pick, so `lo` can't be above `hi`.
+fn range_values(lo: &str, hi: &str) -> Result<Values, String> {
+    let number = |value: &str| {
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("invalid number {:?}", value))
+    };
+    let (values, empty) = match (lo.parse::<i64>(), hi.parse::<i64>()) {
+        (Ok(l), Ok(h)) => (Values::IntRange(l, h), l > h),
+        _ => {
+            let (l, h) = (number(lo)?, number(hi)?);
+            (Values::Range(l, h), l > h || l.is_nan() || h.is_nan())
+        }
+    };
+    if empty {
+        return Err(format!("{}..{} is empty", lo, hi));
+    }
+    Ok(values)
+}
+
+fn parse_size(size: &str) -> Result<(Coord, Coord), String> {
+    let invalid = || format!("expected `<width>x<height>`, got {:?}", size);
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let (width, height): (Coord, Coord) = (
+        width.parse().map_err(|_| invalid())?,
+        height.parse().map_err(|_| invalid())?,
+    );
+    if (width as usize) * (height as usize) < 2 {
+        return Err(format!("{} is too small to play on", size));
+    }
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_documented_example() {
+        let sweep = Sweep::parse(
+            "agent = \"average-path\"\nsizes = [\"6x6\", \"8x8\"]\ngames = 8\nbudget = [500, 2000]\n\
+             wall_contact = \"0.0..1.0\"\nsamples = 10\n",
+        )
+        .unwrap();
+        assert_eq!(sweep.agent, "average-path");
+        assert_eq!(sweep.sizes, [(6, 6), (8, 8)]);
+        assert_eq!(
+            sweep.params,
+            [
+                (
+                    "budget".to_string(),
+                    Values::List(vec!["500".into(), "2000".into()])
+                ),
+                ("wall_contact".to_string(), Values::Range(0.0, 1.0)),
+            ]
+        );
+
+        let trials = sweep.trials();
+        assert_eq!(trials.len(), 20);
+        let wall_contact = trials
+            .iter()
+            .map(|t| t.config.get::<f32>("wall_contact").unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert!(wall_contact.iter().all(|w| (0.0..=1.0).contains(w)));
+        assert!(wall_contact.iter().any(|w| w.fract() != 0.0));
+    }
+
+    #[test]
+    fn grids_cover_every_combination() {
+        let sweep =
+            Sweep::parse("agent = \"tree-search\"\npath_length = [1, 2, 3]\ncompactness = [0, -1]")
+                .unwrap();
+        let trials = sweep.trials();
+        assert_eq!(trials.len(), 6);
+        assert_eq!(
+            trials[1].config.to_string(),
+            "tree-search:path_length=1,compactness=-1"
+        );
+        assert!(Sweep::parse("agent = \"greedy\"\nbudget = \"1..5\"").is_err());
+        assert!(Sweep::parse("sizes = [\"4x4\"]").is_err());
+        assert!(Sweep::parse("agent = \"greedy\"\nsizes = [\"4by4\"]").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_ranges() {
+        let err =
+            Sweep::parse("agent = \"average-path\"\nsamples = 2\nbudget = \"20..10\"").unwrap_err();
+        assert_eq!(err, "line 3: budget: 20..10 is empty");
+        let err =
+            Sweep::parse("agent = \"tree-search\"\nsamples = 2\n\ncompactness = \"1.0..-1.0\"");
+        assert!(err.unwrap_err().starts_with("line 4:"));
+        assert!(Sweep::parse("agent = \"average-path\"\nsamples = 2\nbudget = \"10..10\"").is_ok());
+    }
+
+    #[test]
+    fn integer_ranges_sample_integers() {
+        let sweep =
+            Sweep::parse("agent = \"average-path\"\nbudget = \"10..20\"\nsamples = 30").unwrap();
+        for trial in sweep.trials() {
+            let budget = trial.config.get::<usize>("budget").unwrap().unwrap();
+            assert!((10..=20).contains(&budget));
+        }
+    }
+
+    #[test]
+    fn ranks_and_reports() {
+        let sweep = Sweep::parse(
+            "agent = \"tree-search\"\nsizes = [\"5x5\", \"6x4\"]\ngames = 2\npath_length = [1, 2]\nthreads = 3\n\
+             max_moves_without_apple = 50",
+        )
+        .unwrap();
+        let outcomes = sweep.run(&HeuristicConfig::default()).unwrap();
+        assert_eq!(outcomes.len(), 4);
+        assert!(outcomes
+            .windows(2)
+            .all(|w| w[0].mean_score >= w[1].mean_score));
+        for outcome in &outcomes {
+            assert_eq!(outcome.died + outcome.won + outcome.stalled, 2);
+        }
+
+        let table = sweep.table(&outcomes);
+        assert!(table.starts_with("rank  size  path_length  score"));
+        assert_eq!(table.lines().count(), 5);
+        let csv = sweep.csv(&outcomes);
+        assert!(csv.starts_with("rank,width,height,path_length,mean_score,"));
+        assert_eq!(csv.lines().nth(1).unwrap().split(',').count(), 9);
+
+        let bad = Sweep::parse("agent = \"tree-search\"\nbudget = [5]").unwrap();
+        assert!(bad.run(&HeuristicConfig::default()).is_err());
+    }
+}
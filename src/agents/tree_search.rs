@@ -1,14 +1,33 @@
-use super::Agent;
-use crate::gameplay::*;
+use super::{
+    heuristic::{in_bounds_neighbors, Weights},
+    surviving_action, Agent,
+};
+use crate::{gameplay::*, profile::Work};
 
+use float_ord::FloatOrd;
 use std::{
     cmp::Ordering,
     collections::{BinaryHeap, HashSet, VecDeque},
 };
 
-#[derive(Default)]
 pub struct TreeSearch {
     plan: VecDeque<Action>,
+    weights: Weights,
+}
+
+impl Default for TreeSearch {
+    fn default() -> Self {
+        TreeSearch::with_weights(Weights::tree_search())
+    }
+}
+
+impl TreeSearch {
+    pub fn with_weights(weights: Weights) -> Self {
+        TreeSearch {
+            plan: VecDeque::new(),
+            weights,
+        }
+    }
 }
 
 impl Agent for TreeSearch {
@@ -17,27 +36,49 @@ impl Agent for TreeSearch {
             return a;
         }
 
-        self.plan = generate_plan(game);
+        self.plan = generate_plan(game, &self.weights);
         self.plan.pop_front().unwrap()
     }
+
+    fn plan(&self) -> Option<Vec<Action>> {
+        Some(self.plan.iter().copied().collect())
+    }
+
+    fn reset(&mut self) {
+        self.plan.clear();
+    }
 }
 
-fn generate_plan(game: &SnakeGame) -> VecDeque<Action> {
+#[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+fn generate_plan(game: &SnakeGame, weights: &Weights) -> VecDeque<Action> {
     let mut queue = BinaryHeap::new();
+    // Positions already expanded. Without it a board with no safe way to the apple is searched
+    // forever, going round the same positions.
+    let mut expanded = HashSet::new();
 
     queue.push(State {
         game: game.clone(),
         actions: VecDeque::new(),
+        cost: FloatOrd(weights.cost(game, game, 0)),
     });
 
     loop {
         let state = match queue.pop() {
             Some(s) => s,
-            None => return [Action::GoStraight].into_iter().collect(),
+            None => return [surviving_action(game)].into_iter().collect(),
         };
+        Work::count(|w| w.nodes += 1);
         if state.game.score > game.score {
             return state.actions;
         }
+        let position = (
+            state.game.body.clone(),
+            state.game.heading,
+            state.game.growing,
+        );
+        if !expanded.insert(position) {
+            continue;
+        }
 
         while let Some(head) = queue.peek() {
             if head == &state {
@@ -58,6 +99,7 @@ fn generate_plan(game: &SnakeGame) -> VecDeque<Action> {
             if new_state.is_closed_off() {
                 continue;
             }
+            new_state.cost = FloatOrd(weights.cost(game, &new_state.game, new_state.actions.len()));
             queue.push(new_state);
         }
     }
@@ -67,18 +109,18 @@ fn generate_plan(game: &SnakeGame) -> VecDeque<Action> {
 struct State {
     game: SnakeGame,
     actions: VecDeque<Action>,
+    cost: FloatOrd<f32>,
 }
 
 impl State {
-    fn distance_to_apple(&self) -> u8 {
-        self.game.head().taxicab_distance_to(self.game.apple)
-    }
-
+    // Whether there's too little room left for the snake at its full length.
     fn is_closed_off(&self) -> bool {
-        !self.can_reach_at_least(self.game.body.len() + 1)
+        !self.can_reach_at_least(self.game.body.len() + self.game.growing + 1)
     }
 
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
     fn can_reach_at_least(&self, n: usize) -> bool {
+        Work::count(|w| w.bfs += 1);
         let mut reached = HashSet::new();
         let mut front = VecDeque::new();
         front.push_back(self.game.head());
@@ -91,13 +133,7 @@ impl State {
                 return true;
             }
             front.extend(
-                Heading::iter()
-                    .filter_map(|h| h.move_(cell))
-                    .filter(|cell| match self.game.cell_occupant(*cell) {
-                        None => true,
-                        Some(Occupant::Body) => false,
-                        Some(Occupant::Apple) => true,
-                    }),
+                in_bounds_neighbors(&self.game, cell).filter(|cell| !self.game.is_blocked(*cell)),
             );
         }
 
@@ -105,16 +141,11 @@ impl State {
     }
 }
 
+// Cheapest first by the weighted `cost`, which replaced ordering by score, then distance to the
+// apple, then plan length, so the tree-search weights steer the search like any other agent's.
 impl Ord for State {
     fn cmp(&self, other: &Self) -> Ordering {
-        Ordering::Equal
-            .then_with(|| self.game.score.cmp(&other.game.score))
-            .then_with(|| {
-                self.distance_to_apple()
-                    .cmp(&other.distance_to_apple())
-                    .reverse()
-            })
-            .then_with(|| self.actions.len().cmp(&other.actions.len()).reverse())
+        self.cost.cmp(&other.cost).reverse()
     }
 }
 
@@ -0,0 +1,192 @@
+use super::{
+    average_path::AveragePath, heuristic::Weights, neural::gaussian, par_map, play_out,
+    tree_search::TreeSearch, Agent,
+};
+use crate::gameplay::*;
+
+use rand::{prelude::*, rngs::SmallRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    AveragePath,
+    TreeSearch,
+}
+
+impl Target {
+    pub fn default_weights(self) -> Weights {
+        match self {
+            Target::AveragePath => Weights::average_path(),
+            Target::TreeSearch => Weights::tree_search(),
+        }
+    }
+
+    fn agent(self, weights: Weights, budget: usize) -> Box<dyn Agent> {
+        match self {
+            Target::AveragePath => Box::new(AveragePath::with_weights(weights).with_budget(budget)),
+            Target::TreeSearch => Box::new(TreeSearch::with_weights(weights)),
+        }
+    }
+
+    // Keeps the weights the agent relies on for pruning in their valid range, see
+    // `Weights::check_pruning`.
+    fn constrain(self, weights: Weights) -> Weights {
+        match self {
+            Target::AveragePath => Weights {
+                path_length: weights.path_length.max(0.0),
+                average_moves: weights.average_moves.max(0.0),
+                ..weights
+            },
+            Target::TreeSearch => weights,
+        }
+    }
+}
+
+/// Genetic algorithm over heuristic weights, scored by the mean score on seeded benchmark games.
+#[derive(Debug, Clone)]
+pub struct Tuning {
+    pub target: Target,
+    pub population: usize,
+    pub generations: usize,
+    pub elites: usize,
+    pub mutation_strength: f32,
+    pub games: usize,
+    pub width: Coord,
+    pub height: Coord,
+    pub seed: u64,
+    pub max_moves_without_apple: usize,
+    // Search budget per decision for agents that support one, so that poor weights cannot make
+    // a single game run for minutes.
+    pub search_budget: usize,
+    pub threads: usize,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            target: Target::AveragePath,
+            population: 24,
+            generations: 10,
+            elites: 4,
+            mutation_strength: 0.2,
+            games: 8,
+            // AveragePath can search for a very long time on some larger positions.
+            width: 6,
+            height: 6,
+            seed: 0,
+            max_moves_without_apple: 500,
+            search_budget: 5_000,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Tuning {
+    /// Runs the optimizer, calling `on_generation` with each generation's best weights and
+    /// fitness, and returns the best weights found.
+    pub fn run(&self, mut on_generation: impl FnMut(usize, &Weights, f32)) -> Weights {
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        let initial = self.target.default_weights();
+
+        let mut population = vec![initial];
+        while population.len() < self.population {
+            population.push(self.mutate(initial, &mut rng));
+        }
+
+        let mut best = (initial, f32::NEG_INFINITY);
+        for generation in 0..self.generations {
+            // Unlike evolving networks, weights are scored on the same boards every generation so
+            // the best result stays comparable across generations.
+            let fitness = self.evaluate(&population);
+            let mut ranked = population.into_iter().zip(fitness).collect::<Vec<_>>();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+            if ranked[0].1 > best.1 {
+                best = ranked[0];
+            }
+            on_generation(generation, &ranked[0].0, ranked[0].1);
+
+            let parents = &ranked[..self.elites.clamp(1, ranked.len())];
+            population = parents.iter().map(|(w, _)| *w).collect();
+            while population.len() < self.population {
+                let a = parents.choose(&mut rng).unwrap().0.to_array();
+                let b = parents.choose(&mut rng).unwrap().0.to_array();
+                let t = rng.gen::<f32>();
+                let child = Weights::from_array(std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t));
+                population.push(self.mutate(child, &mut rng));
+            }
+        }
+        best.0
+    }
+
+    fn mutate(&self, weights: Weights, rng: &mut impl Rng) -> Weights {
+        let array = weights.to_array().map(|w| {
+            // Scale relative to the weight so huge priority weights and small fractional weights
+            // both move. Zero weights get a unit scale so unused features can be switched on.
+            let scale = if w == 0.0 { 1.0 } else { w.abs() };
+            w + gaussian(rng) * self.mutation_strength * scale
+        });
+        self.target.constrain(Weights::from_array(array))
+    }
+
+    fn evaluate(&self, population: &[Weights]) -> Vec<f32> {
+        par_map(population, self.threads, |weights| self.fitness(*weights))
+    }
+
+    pub fn fitness(&self, weights: Weights) -> f32 {
+        (0..self.games)
+            .map(|game| {
+                let mut agent = self.target.agent(weights, self.search_budget);
+                let mut game =
+                    SnakeGame::seeded(self.width, self.height, self.seed.wrapping_add(game as u64));
+                play_out(agent.as_mut(), &mut game, self.max_moves_without_apple);
+                game.score as f32
+            })
+            .sum::<f32>()
+            / self.games.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuned_weights_do_at_least_as_well_as_the_defaults() {
+        let tuning = Tuning {
+            population: 4,
+            generations: 2,
+            elites: 2,
+            games: 2,
+            width: 5,
+            height: 5,
+            max_moves_without_apple: 20,
+            search_budget: 100,
+            threads: 2,
+            ..Default::default()
+        };
+        let mut generations = 0;
+        let tuned = tuning.run(|_, _, _| generations += 1);
+        assert_eq!(generations, 2);
+        assert!(tuning.fitness(tuned) >= tuning.fitness(Target::AveragePath.default_weights()));
+    }
+
+    #[test]
+    fn mutation_keeps_pruning_weights_non_negative() {
+        let tuning = Tuning {
+            mutation_strength: 10.0,
+            ..Default::default()
+        };
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut weights = Target::AveragePath.default_weights();
+        for _ in 0..100 {
+            weights = tuning.mutate(weights, &mut rng);
+            assert_eq!(weights.check_pruning(), Ok(()));
+        }
+
+        let negative = Weights {
+            path_length: -1.0,
+            ..Weights::tree_search()
+        };
+        assert_eq!(Target::TreeSearch.constrain(negative), negative);
+        assert_eq!(Target::AveragePath.constrain(negative).path_length, 0.0);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{agents::Agent, gameplay::*, render};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Runs several agents side by side on boards created from the same seed, one pane per agent.
+pub struct Dashboard {
+    pub width: Coord,
+    pub height: Coord,
+    pub seed: u64,
+    pub tick: Duration,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Dashboard {
+            width: 10,
+            height: 10,
+            seed: rand::random(),
+            tick: Duration::from_millis(50),
+        }
+    }
+}
+
+struct Pane<'a> {
+    name: &'a str,
+    agent: &'a mut dyn Agent,
+    game: SnakeGame,
+    outcome: Option<Terminal>,
+    // Decision time of every move, and moves taken to reach each apple.
+    latencies: Vec<Duration>,
+    moves_per_apple: Vec<usize>,
+    last_apple: usize,
+}
+
+impl Pane<'_> {
+    fn step(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        let start = Instant::now();
+        let action = self.agent.action(&self.game);
+        self.latencies.push(start.elapsed());
+
+        let score = self.game.score;
+        self.outcome = self.game.do_action(action);
+        if self.game.score > score {
+            self.moves_per_apple.push(self.game.moves - self.last_apple);
+            self.last_apple = self.game.moves;
+        }
+    }
+
+    fn lines(&self, width: usize) -> Vec<String> {
+        let status = match self.outcome {
+            Some(terminal) => format!("{:?}", terminal),
+            None => "playing".to_string(),
+        };
+        let mean_moves = self.last_apple as f32 / self.game.score.max(1) as f32;
+        let latency = self.latencies.last().copied().unwrap_or_default();
+
+        let mut lines = vec![format!("{} ({})", self.name, status)];
+        // Skip the score line `dbg_string` starts with, since the pane shows its own.
+        lines.extend(
+            render::dbg_string(&self.game)
+                .lines()
+                .skip(1)
+                .map(String::from),
+        );
+        lines.push(format!(
+            "score {}  moves {}",
+            self.game.score, self.game.moves
+        ));
+        lines.push(format!("moves/apple {:.1}", mean_moves));
+        lines.push(sparkline(&self.moves_per_apple, width));
+        lines.push(format!("decision {:.2?}", latency));
+        let micros = self
+            .latencies
+            .iter()
+            .map(|d| d.as_micros() as usize)
+            .collect::<Vec<_>>();
+        lines.push(sparkline(&micros, width));
+        lines
+    }
+}
+
+// The last `width` values as bars scaled to the largest of them.
+fn sparkline(values: &[usize], width: usize) -> String {
+    let recent = &values[values.len().saturating_sub(width)..];
+    let max = recent.iter().copied().max().unwrap_or(0).max(1);
+    recent
+        .iter()
+        .map(|&v| SPARKS[v * (SPARKS.len() - 1) / max])
+        .collect()
+}
+
+impl Dashboard {
+    /// Plays every agent until they've all finished, then waits for `q`. `p` pauses.
+    pub fn run(&self, agents: Vec<(&str, &mut dyn Agent)>) -> io::Result<()> {
+        let _screen = render::FullScreen::enter()?;
+        self.show(agents)
+    }
+
+    fn show(&self, agents: Vec<(&str, &mut dyn Agent)>) -> io::Result<()> {
+        let mut screen = render::Terminal::default();
+        let mut panes = agents
+            .into_iter()
+            .map(|(name, agent)| Pane {
+                name,
+                agent,
+                game: SnakeGame::seeded(self.width, self.height, self.seed),
+                outcome: None,
+                latencies: Vec::new(),
+                moves_per_apple: Vec::new(),
+                last_apple: 0,
+            })
+            .collect::<Vec<_>>();
+        let pane_width = (self.width as usize + 2).max(24);
+        let mut paused = false;
+
+        loop {
+            let finished = panes.iter().all(|pane| pane.outcome.is_some());
+            screen.draw(&self.frame(&panes, pane_width, paused, finished))?;
+
+            let deadline = Instant::now() + self.tick;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                if !event::poll(timeout)? {
+                    break;
+                }
+                let key = match event::read()? {
+                    Event::Key(key) if key.kind != KeyEventKind::Release => key,
+                    _ => continue,
+                };
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('p') | KeyCode::Char(' ') => {
+                        paused = !paused;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+
+            if !paused && Instant::now() >= deadline {
+                for pane in &mut panes {
+                    pane.step();
+                }
+            }
+        }
+    }
+
+    fn frame(&self, panes: &[Pane], pane_width: usize, paused: bool, finished: bool) -> String {
+        let columns = panes
+            .iter()
+            .map(|pane| pane.lines(pane_width))
+            .collect::<Vec<_>>();
+        let rows = columns.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut s = format!(
+            "seed {}  {}\n\n",
+            self.seed,
+            match (finished, paused) {
+                (true, _) => "finished. q: quit",
+                (false, true) => "paused. p: resume, q: quit",
+                (false, false) => "p: pause, q: quit",
+            }
+        );
+        for row in 0..rows {
+            for column in &columns {
+                let line = column.get(row).map(String::as_str).unwrap_or_default();
+                s.push_str(line);
+                let padding = pane_width.saturating_sub(line.chars().count()) + 2;
+                s.extend(std::iter::repeat_n(' ', padding));
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::greedy::Greedy;
+
+    #[test]
+    fn sparkline_scales_to_the_largest_recent_value() {
+        assert_eq!(sparkline(&[0, 7, 14], 10), "▁▄█");
+        assert_eq!(sparkline(&[1, 2, 3, 4, 5, 6, 7, 8], 8), "▁▂▃▄▅▆▇█");
+        // Only the last `width` values are drawn, so an old spike doesn't flatten the rest.
+        assert_eq!(sparkline(&[100, 1, 2], 2), "▄█");
+        assert_eq!(sparkline(&[0, 0], 4), "▁▁");
+        assert_eq!(sparkline(&[], 4), "");
+    }
+
+    fn pane<'a>(name: &'a str, agent: &'a mut dyn Agent) -> Pane<'a> {
+        Pane {
+            name,
+            agent,
+            game: SnakeGame::seeded(4, 3, 0),
+            outcome: None,
+            latencies: Vec::new(),
+            moves_per_apple: Vec::new(),
+            last_apple: 0,
+        }
+    }
+
+    // The frame's rows cut into columns of `width`, with the padding between them trimmed.
+    fn columns(frame: &str, panes: usize, width: usize) -> Vec<Vec<String>> {
+        frame
+            .lines()
+            .skip(2)
+            .map(|row| {
+                let chars = row.chars().collect::<Vec<_>>();
+                assert_eq!(chars.len(), panes * width, "{:?}", row);
+                chars
+                    .chunks(width)
+                    .map(|column| column.iter().collect::<String>().trim_end().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn panes_side_by_side() {
+        let dashboard = Dashboard {
+            width: 4,
+            height: 3,
+            seed: 0,
+            ..Default::default()
+        };
+        let (mut a, mut b, mut c) = (Greedy, Greedy, Greedy);
+
+        let mut panes = vec![pane("a", &mut a), pane("b", &mut b)];
+        panes[1].outcome = Some(Terminal::Died);
+        let frame = dashboard.frame(&panes, 24, false, false);
+        assert!(frame.starts_with("seed 0  p: pause, q: quit\n\n"));
+        let rows = columns(&frame, 2, 26);
+        let board = render::dbg_string(&panes[0].game);
+        assert_eq!(rows.len(), board.lines().count() + 5);
+        assert_eq!(rows[0], ["a (playing)", "b (Died)"]);
+        for (row, line) in rows[1..].iter().zip(board.lines().skip(1)) {
+            assert_eq!(row, &[line, line]);
+        }
+
+        panes.push(pane("c", &mut c));
+        panes[2].step();
+        let frame = dashboard.frame(&panes, 24, true, false);
+        assert!(frame.starts_with("seed 0  paused. p: resume, q: quit\n\n"));
+        let rows = columns(&frame, 3, 26);
+        assert_eq!(rows[0], ["a (playing)", "b (Died)", "c (playing)"]);
+        assert_eq!(
+            rows[6],
+            ["score 0  moves 0", "score 0  moves 0", "score 0  moves 1"]
+        );
+        // One decision so far, drawn as a single bar under the third pane only.
+        assert_eq!(rows[10][..2], ["", ""]);
+        assert_eq!(rows[10][2].chars().count(), 1);
+    }
+}
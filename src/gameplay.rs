@@ -1,46 +1,188 @@
-use rand::{prelude::*, rngs::SmallRng};
-use std::collections::VecDeque;
+use rand::prelude::*;
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+mod board;
+mod compact;
+mod rng;
+mod snapshot;
+mod spawn;
+pub use compact::*;
+pub use rng::GameRng;
+pub use snapshot::Snapshot;
+pub use spawn::*;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Snapshot", try_from = "Snapshot")
+)]
 pub struct SnakeGame {
     pub score: usize,
     pub moves: usize,
 
-    pub width: u8,
-    pub height: u8,
+    pub width: Coord,
+    pub height: Coord,
 
     pub apple: Cell,
     pub body: VecDeque<Cell>,
     pub heading: Heading,
 
-    rng: SmallRng,
+    pub rules: Rules,
+    pub spawn: Spawn,
+    // Segments still to be added, one per move, from apples already eaten.
+    pub growing: usize,
+
+    rng: GameRng,
+}
+
+/// Variations on the rules of the game. The default is the strict original ruleset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rules {
+    /// Whether the head may move into the cell the tail is leaving on the same move.
+    pub tail_follow: bool,
+    /// Segments added for each apple eaten.
+    pub growth: usize,
+    /// Length of the snake at the start, laid out behind the head as `Spawn::shape` says.
+    pub starting_length: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            tail_follow: false,
+            growth: 1,
+            starting_length: 1,
+        }
+    }
+}
+
+/// Comma-separated, e.g. `tail-follow,growth=2,length=3`. Options left out keep their default.
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Rules::default();
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let number = |value: &str| {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid number in {:?}", option))
+            };
+            match option.split_once('=') {
+                None if option == "tail-follow" => rules.tail_follow = true,
+                None if option == "strict" => rules.tail_follow = false,
+                Some(("growth", value)) => rules.growth = number(value)?,
+                Some(("length", value)) => match number(value)? {
+                    0 => return Err("length must be at least 1".to_string()),
+                    n => rules.starting_length = n,
+                },
+                _ => return Err(format!("unknown rule {:?}", option)),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// The inverse of `from_str`, listing only options that differ from the default.
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let default = Rules::default();
+        let mut options = Vec::new();
+        if self.tail_follow {
+            options.push("tail-follow".to_string());
+        }
+        if self.growth != default.growth {
+            options.push(format!("growth={}", self.growth));
+        }
+        if self.starting_length != default.starting_length {
+            options.push(format!("length={}", self.starting_length));
+        }
+        write!(f, "{}", options.join(","))
+    }
 }
 
 impl SnakeGame {
-    pub fn random(width: u8, height: u8) -> SnakeGame {
+    /// Panics if the board has fewer than two cells, leaving no room for the apple.
+    pub fn random(width: Coord, height: Coord) -> SnakeGame {
+        SnakeGame::random_with_rules(width, height, Rules::default())
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Panics if the board has fewer than two cells, leaving no room for the apple.
+    pub fn seeded(width: Coord, height: Coord, seed: u64) -> SnakeGame {
+        SnakeGame::seeded_with_rules(width, height, seed, Rules::default())
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fails if the starting body doesn't fit on the board.
+    pub fn random_with_rules(
+        width: Coord,
+        height: Coord,
+        rules: Rules,
+    ) -> Result<SnakeGame, String> {
+        SnakeGame::with_rng(
+            width,
+            height,
+            rules,
+            Spawn::default(),
+            GameRng::from_entropy(),
+        )
+    }
+
+    /// Fails if the starting body doesn't fit on the board.
+    pub fn seeded_with_rules(
+        width: Coord,
+        height: Coord,
+        seed: u64,
+        rules: Rules,
+    ) -> Result<SnakeGame, String> {
+        SnakeGame::seeded_with_spawn(width, height, seed, rules, Spawn::default())
+    }
+
+    fn with_rng(
+        width: Coord,
+        height: Coord,
+        rules: Rules,
+        spawn: Spawn,
+        rng: GameRng,
+    ) -> Result<SnakeGame, String> {
         let mut game = SnakeGame {
             width,
             height,
-            rng: SmallRng::from_entropy(),
+            rules,
+            spawn,
+            rng,
 
             score: 0,
             moves: 0,
+            growing: 0,
             apple: Cell::default(),
             body: VecDeque::new(),
             heading: Heading::default(),
         };
 
-        game.apple = game.gen_cell();
-
-        let head = game.gen_open_cell().unwrap();
-        game.body.push_back(head);
-
-        game.heading = [Heading::North, Heading::South, Heading::East, Heading::West]
-            .choose(&mut game.rng)
-            .unwrap()
-            .clone();
+        // The body goes down first so a long one has the whole board to fit on.
+        if rules.starting_length > 1 || spawn != Spawn::default() {
+            game.place_body(spawn)?;
+        } else {
+            let head = game.gen_cell();
+            game.body.push_back(head);
+
+            game.heading = [Heading::North, Heading::South, Heading::East, Heading::West]
+                .choose(&mut game.rng)
+                .copied()
+                .unwrap();
+        }
 
-        game
+        game.apple = game.gen_open_cell().ok_or_else(|| {
+            format!(
+                "no room for the apple beside a snake of length {} on a {}x{} board",
+                rules.starting_length, width, height
+            )
+        })?;
+        Ok(game)
     }
 
     fn gen_cell(&mut self) -> Cell {
@@ -49,13 +191,17 @@ impl SnakeGame {
         Cell(x, y)
     }
 
+    // A cell off the body, for the next apple. The last apple is under the head by the time this
+    // is called, or not placed yet, so it doesn't need avoiding.
     fn gen_open_cell(&mut self) -> Option<Cell> {
         let cell = self.gen_cell();
-        if self.cell_occupant(cell) == None {
+        if !self.body.contains(&cell) {
             return Some(cell);
         }
 
-        self.open_cells()
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| Cell(col, row)))
+            .filter(|cell| !self.body.contains(cell))
             .collect::<Vec<_>>()
             .choose(&mut self.rng)
             .cloned()
@@ -64,7 +210,7 @@ impl SnakeGame {
     pub fn open_cells(&self) -> impl Iterator<Item = Cell> + '_ {
         (0..self.height)
             .flat_map(|row| (0..self.width).map(move |col| Cell(col, row)))
-            .filter(|cell| self.cell_occupant(*cell) == None)
+            .filter(|cell| self.cell_occupant(*cell).is_none())
     }
 
     pub fn non_body_cells(&self) -> impl Iterator<Item = Cell> + '_ {
@@ -82,6 +228,21 @@ impl SnakeGame {
         None
     }
 
+    /// Whether the head would collide with the body by moving into `cell` on the next move.
+    ///
+    /// Body cells are blocked except for the tail under `Rules::tail_follow`, when the snake isn't
+    /// growing and so the tail is leaving.
+    pub fn is_blocked(&self, cell: Cell) -> bool {
+        match self.cell_occupant(cell) {
+            Some(Occupant::Body) => !(self.tail_leaves() && cell == self.body[0]),
+            None | Some(Occupant::Apple) => false,
+        }
+    }
+
+    fn tail_leaves(&self) -> bool {
+        self.rules.tail_follow && self.growing == 0 && self.body.len() > 1
+    }
+
     #[must_use]
     pub fn do_action(&mut self, action: Action) -> Option<Terminal> {
         self.moves += 1;
@@ -92,30 +253,38 @@ impl SnakeGame {
             Some(next_cell) => next_cell,
             None => return Some(Terminal::Died),
         };
+        if self.is_blocked(next_cell) {
+            return Some(Terminal::Died);
+        }
 
-        match self.cell_occupant(next_cell) {
-            None => {
-                self.body.pop_front();
-                self.body.push_back(next_cell);
-            }
-            Some(Occupant::Apple) => {
-                self.score += 1;
-                self.body.push_back(self.apple);
-                self.apple = match self.gen_open_cell() {
-                    Some(c) => c,
-                    None => return Some(Terminal::Won),
-                };
-            }
-            Some(Occupant::Body) => return Some(Terminal::Died),
+        let ate = next_cell == self.apple;
+        if ate {
+            self.score += 1;
+            self.growing += self.rules.growth;
+        }
+        if self.growing > 0 {
+            self.growing -= 1;
+        } else {
+            self.body.pop_front();
+        }
+        self.body.push_back(next_cell);
+
+        if ate {
+            self.apple = match self.gen_open_cell() {
+                Some(c) => c,
+                None => return Some(Terminal::Won),
+            };
         }
 
         None
     }
 
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
     pub fn do_many(
         &self,
         actions: impl IntoIterator<Item = impl core::borrow::Borrow<Action>>,
     ) -> Result<Self, Terminal> {
+        crate::profile::Work::count(|w| w.clones += 1);
         let mut clone = self.clone();
         for action in actions {
             if let Some(term) = clone.do_action(*action.borrow()) {
@@ -129,7 +298,7 @@ impl SnakeGame {
         *self.body.iter().next_back().unwrap()
     }
 
-    fn cell_delta(&self, cell: Cell, heading: Heading) -> Option<Cell> {
+    pub fn cell_delta(&self, cell: Cell, heading: Heading) -> Option<Cell> {
         let next = heading.move_(cell)?;
         if next.0 >= self.width || next.1 >= self.height {
             return None;
@@ -138,8 +307,28 @@ impl SnakeGame {
     }
 }
 
+// Serialized through `Snapshot`, so loading checks the game the same way.
+impl From<SnakeGame> for Snapshot {
+    fn from(game: SnakeGame) -> Snapshot {
+        game.snapshot()
+    }
+}
+
+impl TryFrom<Snapshot> for SnakeGame {
+    type Error = String;
+
+    fn try_from(snapshot: Snapshot) -> Result<SnakeGame, String> {
+        SnakeGame::from_snapshot(snapshot)
+    }
+}
+
+/// A column or row, and a board's width or height. Boards can be up to `Coord::MAX` cells across,
+/// though the searches get slow long before that.
+pub type Coord = u16;
+
 #[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
-pub struct Cell(pub u8, pub u8);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell(pub Coord, pub Coord);
 
 impl Cell {
     pub fn heading_toward(self, other: Cell) -> Option<Heading> {
@@ -154,8 +343,9 @@ impl Cell {
         }
     }
 
-    pub fn taxicab_distance_to(self, other: Cell) -> u8 {
-        abs_diff(self.0, other.0) + abs_diff(self.1, other.1)
+    /// Wider than `Coord`, since it can be up to a board's width plus its height.
+    pub fn taxicab_distance_to(self, other: Cell) -> u32 {
+        self.0.abs_diff(other.0) as u32 + self.1.abs_diff(other.1) as u32
     }
 
     #[allow(unused)]
@@ -164,15 +354,8 @@ impl Cell {
     }
 }
 
-fn abs_diff(a: u8, b: u8) -> u8 {
-    if a > b {
-        a - b
-    } else {
-        b - a
-    }
-}
-
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     TurnLeft,
     TurnRight,
@@ -186,37 +369,35 @@ impl Action {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Terminal {
     Won,
     Died,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Occupant {
     Apple,
     Body,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Heading {
     North,
     South,
+    #[default]
     East,
     West,
 }
 
-impl Default for Heading {
-    fn default() -> Self {
-        Heading::East
-    }
-}
-
 impl Heading {
     pub fn iter() -> impl Iterator<Item = Heading> {
         [Heading::North, Heading::South, Heading::East, Heading::West].into_iter()
     }
 
-    fn after(self, action: Action) -> Heading {
+    pub fn after(self, action: Action) -> Heading {
         use Heading::*;
 
         match action {
@@ -246,13 +427,380 @@ impl Heading {
         }
     }
 
+    pub fn opposite(self) -> Heading {
+        match self {
+            Heading::North => Heading::South,
+            Heading::South => Heading::North,
+            Heading::East => Heading::West,
+            Heading::West => Heading::East,
+        }
+    }
+
     pub fn turn_towards(self, other: Heading) -> Option<Action> {
         if self == other {
             return Some(Action::GoStraight);
         }
         [Action::TurnRight, Action::TurnLeft]
             .into_iter()
-            .filter(|a| self.after(*a) == other)
-            .next()
+            .find(|a| self.after(*a) == other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const ACTIONS: [Action; 3] = [Action::GoStraight, Action::TurnLeft, Action::TurnRight];
+
+    fn action() -> impl Strategy<Value = Action> {
+        prop::sample::select(&ACTIONS[..])
+    }
+
+    fn check_invariants(game: &SnakeGame) {
+        let body = game.body.iter().copied().collect::<Vec<_>>();
+        for (i, cell) in body.iter().enumerate() {
+            assert!(
+                cell.0 < game.width && cell.1 < game.height,
+                "{:?} off board",
+                cell
+            );
+            assert!(!body[i + 1..].contains(cell), "{:?} repeated in body", cell);
+        }
+        for pair in body.windows(2) {
+            assert_eq!(
+                pair[0].taxicab_distance_to(pair[1]),
+                1,
+                "body not contiguous"
+            );
+        }
+        if let [.., neck, head] = body[..] {
+            assert_eq!(neck.heading_toward(head), Some(game.heading));
+        }
+
+        let rules = game.rules;
+        assert_eq!(
+            body.len() + game.growing,
+            rules.starting_length + rules.growth * game.score
+        );
+        assert!(!body.contains(&game.apple), "apple on body");
+        assert!(game.apple.0 < game.width && game.apple.1 < game.height);
+    }
+
+    fn rules() -> impl Strategy<Value = Rules> {
+        (any::<bool>(), 0usize..4, 1usize..5).prop_map(|(tail_follow, growth, starting_length)| {
+            Rules {
+                tail_follow,
+                growth,
+                starting_length,
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn random_actions_keep_invariants(
+            width in 1..12 as Coord,
+            height in 1..12 as Coord,
+            seed in any::<u64>(),
+            rules in rules(),
+            actions in prop::collection::vec(action(), 0..300),
+        ) {
+            let mut game = match SnakeGame::seeded_with_rules(width, height, seed, rules) {
+                Ok(game) => game,
+                Err(e) => {
+                    // A straight body fits along the longer side, with room left for the apple.
+                    let length = rules.starting_length;
+                    prop_assert!(
+                        length > width.max(height) as usize
+                            || length >= width as usize * height as usize,
+                        "{}",
+                        e
+                    );
+                    return Ok(());
+                }
+            };
+            check_invariants(&game);
+
+            for (i, &action) in actions.iter().enumerate() {
+                let score = game.score;
+                let outcome = game.do_action(action);
+                prop_assert_eq!(game.moves, i + 1);
+                match outcome {
+                    None => check_invariants(&game),
+                    Some(Terminal::Won) => {
+                        prop_assert_eq!(game.open_cells().count(), 0);
+                        break;
+                    }
+                    Some(Terminal::Died) => {
+                        prop_assert_eq!(game.score, score);
+                        break;
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn do_many_matches_do_action(
+            seed in any::<u64>(),
+            actions in prop::collection::vec(action(), 0..100),
+        ) {
+            let start = SnakeGame::seeded(8, 8, seed);
+            let mut game = start.clone();
+            let expected = actions
+                .iter()
+                .find_map(|&action| game.do_action(action))
+                .map_or(Ok(game), Err);
+            prop_assert_eq!(start.do_many(&actions), expected);
+        }
+
+        #[test]
+        fn rules_round_trip(rules in rules()) {
+            prop_assert_eq!(rules.to_string().parse::<Rules>(), Ok(rules));
+        }
+
+        #[test]
+        fn seeded_games_are_reproducible(seed in any::<u64>()) {
+            prop_assert_eq!(SnakeGame::seeded(10, 10, seed), SnakeGame::seeded(10, 10, seed));
+        }
+    }
+
+    #[test]
+    fn after_table() {
+        use Heading::*;
+
+        let table = [
+            (North, [North, West, East]),
+            (South, [South, East, West]),
+            (East, [East, North, South]),
+            (West, [West, South, North]),
+        ];
+        for (heading, expected) in table {
+            for (action, expected) in ACTIONS.into_iter().zip(expected) {
+                assert_eq!(
+                    heading.after(action),
+                    expected,
+                    "{:?} {:?}",
+                    heading,
+                    action
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn after_turns_compose() {
+        for heading in Heading::iter() {
+            assert_eq!(
+                heading.after(Action::TurnLeft).after(Action::TurnRight),
+                heading
+            );
+            assert_eq!(
+                heading.after(Action::TurnRight).after(Action::TurnLeft),
+                heading
+            );
+            assert_eq!(heading.opposite().opposite(), heading);
+            for action in [Action::TurnLeft, Action::TurnRight] {
+                assert_eq!(heading.after(action).after(action), heading.opposite());
+                let turned = (0..4).fold(heading, |h, _| h.after(action));
+                assert_eq!(turned, heading);
+                assert_ne!(heading.after(action), heading);
+                assert_ne!(heading.after(action).after(action), heading);
+            }
+        }
+    }
+
+    #[test]
+    fn turn_towards_every_pair() {
+        for from in Heading::iter() {
+            for to in Heading::iter() {
+                match from.turn_towards(to) {
+                    Some(action) => assert_eq!(from.after(action), to),
+                    None => assert_eq!(to, from.opposite(), "{:?} to {:?}", from, to),
+                }
+            }
+            assert_eq!(from.turn_towards(from), Some(Action::GoStraight));
+        }
+    }
+
+    #[test]
+    fn heading_toward_every_pair() {
+        let cells = (0..6).flat_map(|x| (0..6).map(move |y| Cell(x, y)));
+        for from in cells.clone() {
+            for to in cells.clone() {
+                match from.heading_toward(to) {
+                    None => assert_eq!(from, to),
+                    Some(heading) => {
+                        let next = heading.move_(from).unwrap();
+                        assert_eq!(
+                            next.taxicab_distance_to(to) + 1,
+                            from.taxicab_distance_to(to),
+                            "{:?} from {:?} to {:?}",
+                            heading,
+                            from,
+                            to
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn heading_toward_prefers_horizontal() {
+        assert_eq!(Cell(1, 1).heading_toward(Cell(3, 0)), Some(Heading::East));
+        assert_eq!(Cell(1, 1).heading_toward(Cell(0, 3)), Some(Heading::West));
+        assert_eq!(Cell(1, 1).heading_toward(Cell(1, 3)), Some(Heading::South));
+        assert_eq!(Cell(1, 1).heading_toward(Cell(1, 0)), Some(Heading::North));
+    }
+
+    #[test]
+    fn cell_delta_every_edge() {
+        for (width, height) in [(1, 2), (2, 1), (1, 5), (5, 1), (4, 7), (255, 255), (300, 2)] {
+            let game = SnakeGame::seeded(width, height, 0);
+            let xs = [0, 1, width / 2, width - 1];
+            let ys = [0, 1, height / 2, height - 1];
+            for x in xs.into_iter().filter(|&x| x < width) {
+                for y in ys.into_iter().filter(|&y| y < height) {
+                    let cell = Cell(x, y);
+                    let expect = |dx: i32, dy: i32| {
+                        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                        let inside =
+                            (0..width as i32).contains(&nx) && (0..height as i32).contains(&ny);
+                        inside.then_some(Cell(nx as Coord, ny as Coord))
+                    };
+                    assert_eq!(game.cell_delta(cell, Heading::North), expect(0, -1));
+                    assert_eq!(game.cell_delta(cell, Heading::South), expect(0, 1));
+                    assert_eq!(game.cell_delta(cell, Heading::East), expect(1, 0));
+                    assert_eq!(game.cell_delta(cell, Heading::West), expect(-1, 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn move_stops_at_coordinate_limits() {
+        assert_eq!(Heading::West.move_(Cell(0, 3)), None);
+        assert_eq!(Heading::North.move_(Cell(3, 0)), None);
+        assert_eq!(Heading::East.move_(Cell(Coord::MAX, 3)), None);
+        assert_eq!(Heading::South.move_(Cell(3, Coord::MAX)), None);
+    }
+
+    #[test]
+    fn boards_wider_than_a_byte() {
+        let far = Cell(Coord::MAX, Coord::MAX);
+        assert_eq!(Cell(0, 0).taxicab_distance_to(far), 2 * Coord::MAX as u32);
+
+        let mut game = SnakeGame::seeded(300, 260, 5);
+        let start = game.head();
+        for _ in 0..10 {
+            let action = Action::iter()
+                .find(|a| game.clone().do_action(*a).is_none())
+                .unwrap();
+            assert_eq!(game.do_action(action), None);
+        }
+        assert!(game.head().taxicab_distance_to(start) <= 10);
+        assert_eq!(
+            game.to_string().parse::<SnakeGame>().unwrap().body,
+            game.body
+        );
+    }
+
+    // A square loop where turning right moves the head into the tail.
+    fn chasing_tail(rules: Rules) -> SnakeGame {
+        let mut game = SnakeGame::seeded_with_rules(4, 4, 0, rules).unwrap();
+        game.body = [Cell(1, 2), Cell(1, 1), Cell(2, 1), Cell(2, 2)].into();
+        game.heading = Heading::South;
+        game.apple = Cell(3, 3);
+        game.score = 3;
+        game
+    }
+
+    #[test]
+    fn moving_into_the_tail_dies() {
+        let mut game = chasing_tail(Rules::default());
+        assert!(game.is_blocked(Cell(1, 2)));
+        assert_eq!(game.do_action(Action::TurnRight), Some(Terminal::Died));
+    }
+
+    #[test]
+    fn tail_follow_allows_moving_into_the_tail() {
+        let rules = Rules {
+            tail_follow: true,
+            ..Default::default()
+        };
+        let mut game = chasing_tail(rules);
+        assert!(!game.is_blocked(Cell(1, 2)));
+        assert_eq!(game.do_action(Action::TurnRight), None);
+        assert_eq!(game.head(), Cell(1, 2));
+        assert_eq!(game.body.len(), 4);
+
+        // The tail stays put while growing, so it can't be followed.
+        let mut game = chasing_tail(rules);
+        game.growing = 1;
+        assert!(game.is_blocked(Cell(1, 2)));
+        assert_eq!(game.do_action(Action::TurnRight), Some(Terminal::Died));
+    }
+
+    #[test]
+    fn growth_spreads_over_moves() {
+        let rules = Rules {
+            growth: 3,
+            ..Default::default()
+        };
+        let mut game = SnakeGame::seeded_with_rules(5, 1, 0, rules).unwrap();
+        game.body = [Cell(0, 0)].into();
+        game.heading = Heading::East;
+        game.apple = Cell(1, 0);
+
+        assert_eq!(game.do_action(Action::GoStraight), None);
+        assert_eq!((game.body.len(), game.growing), (2, 2));
+        game.apple = Cell(0, 0);
+        assert_eq!(game.do_action(Action::GoStraight), None);
+        assert_eq!((game.body.len(), game.growing), (3, 1));
+        assert_eq!(game.do_action(Action::GoStraight), None);
+        assert_eq!((game.body.len(), game.growing), (4, 0));
+    }
+
+    #[test]
+    fn starting_length_lays_body_behind_head() {
+        for seed in 0..50 {
+            let rules = Rules {
+                starting_length: 4,
+                ..Default::default()
+            };
+            let game = SnakeGame::seeded_with_rules(6, 6, seed, rules).unwrap();
+            assert_eq!(game.body.len(), 4);
+            check_invariants(&game);
+        }
+    }
+
+    #[test]
+    fn long_bodies_fill_narrow_boards() {
+        let rules = Rules {
+            starting_length: 4,
+            ..Default::default()
+        };
+        for seed in 0..50 {
+            let game = SnakeGame::seeded_with_rules(1, 5, seed, rules).unwrap();
+            check_invariants(&game);
+            assert_eq!(game.open_cells().count(), 0);
+        }
+        assert!(SnakeGame::seeded_with_rules(1, 4, 0, rules).is_err());
+    }
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!("".parse(), Ok(Rules::default()));
+        assert_eq!(
+            "tail-follow, growth=2,length=3".parse(),
+            Ok(Rules {
+                tail_follow: true,
+                growth: 2,
+                starting_length: 3,
+            })
+        );
+        assert!("length=0".parse::<Rules>().is_err());
+        assert!("wrap".parse::<Rules>().is_err());
     }
 }
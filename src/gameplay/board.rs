@@ -0,0 +1,371 @@
+use super::*;
+
+/// Draws the game as `render::dbg_print` does, followed by a `body:` line when the body can't be
+/// traced from the drawing alone. `from_str` reads it back.
+impl fmt::Display for SnakeGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.draw(&[]))?;
+        let body = self.body.iter().copied().collect::<Vec<_>>();
+        let cells = body[..body.len() - 1].to_vec();
+        if trace(self.head(), self.heading, cells).as_ref() != Ok(&body) {
+            write!(f, "\nbody: {}", format_cells(&body))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a board in the format `Display` writes, with the default rules and later apples placed
+/// from seed 0. See `from_board` for the format.
+impl FromStr for SnakeGame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SnakeGame::from_board(s, 0, Rules::default())
+    }
+}
+
+impl SnakeGame {
+    /// Reads a board like this one, with later apples placed from `seed`:
+    ///
+    /// ```text
+    /// 2/40
+    /// ######
+    /// #  O #
+    /// #++> #
+    /// #+   #
+    /// ######
+    /// ```
+    ///
+    /// The first line is `score/moves`. `^ v < >` is the head pointing the way it's heading, `O`
+    /// the apple, `+` the rest of the body and ` ` or `.` an empty cell. The body runs back from
+    /// the cell behind the head, each step to the only `+` next to it not yet taken. Where there's
+    /// more than one, the drawing is ambiguous and a `body: x,y ...` line after the board has to
+    /// list the body from the tail to the head.
+    ///
+    /// `growing` and the state of the apple generator aren't drawn, so they start fresh.
+    pub fn from_board(text: &str, seed: u64, rules: Rules) -> Result<SnakeGame, String> {
+        let (width, height, position) = parse_board(text)?;
+        SnakeGame::from_position(width, height, seed, rules, position)
+    }
+
+    // The board with the empty cells of `path` drawn as `.`.
+    pub(crate) fn draw(&self, path: &[Cell]) -> String {
+        use std::fmt::Write;
+
+        let mut s = String::with_capacity((self.width as usize + 2) * (self.height as usize + 2));
+        writeln!(&mut s, "{}/{}", self.score, self.moves).unwrap();
+
+        for _ in 0..self.width as usize + 2 {
+            s.push('#');
+        }
+        s.push('\n');
+
+        for row in 0..self.height {
+            s.push('#');
+            for col in 0..self.width {
+                let cell = Cell(col, row);
+                let c = match self.cell_occupant(cell) {
+                    None if path.contains(&cell) => '.',
+                    None => ' ',
+                    Some(Occupant::Body) if self.head() == cell => head_char(self.heading),
+                    Some(Occupant::Body) => '+',
+                    Some(Occupant::Apple) => 'O',
+                };
+                s.push(c);
+            }
+            s.push('#');
+            s.push('\n');
+        }
+
+        for _ in 0..self.width as usize + 2 {
+            s.push('#');
+        }
+
+        s
+    }
+}
+
+fn head_char(heading: Heading) -> char {
+    match heading {
+        Heading::North => '^',
+        Heading::South => 'v',
+        Heading::East => '>',
+        Heading::West => '<',
+    }
+}
+
+// Cells as `x,y` separated by spaces, the way a `body:` line lists them.
+fn format_cells(cells: &[Cell]) -> String {
+    cells
+        .iter()
+        .map(|c| format!("{},{}", c.0, c.1))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_cells(s: &str) -> Result<Vec<Cell>, String> {
+    s.split_whitespace()
+        .map(|cell| {
+            let (x, y) = cell
+                .split_once(',')
+                .ok_or_else(|| format!("expected `x,y`, got {:?}", cell))?;
+            let coordinate = |c: &str| c.parse().map_err(|_| format!("invalid cell {:?}", cell));
+            Ok(Cell(coordinate(x)?, coordinate(y)?))
+        })
+        .collect()
+}
+
+fn parse_board(text: &str) -> Result<(Coord, Coord, Position), String> {
+    let mut lines = text
+        .lines()
+        .map(str::trim_end)
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty());
+    let at = |i: usize| move |e: String| format!("line {}: {}", i + 1, e);
+
+    let (i, header) = lines.next().ok_or("missing `score/moves` line")?;
+    let (score, moves) = header
+        .trim()
+        .split_once('/')
+        .ok_or_else(|| at(i)(format!("expected `score/moves`, got {:?}", header)))?;
+    let score = score
+        .parse()
+        .map_err(|_| at(i)(format!("invalid score {:?}", score)))?;
+    let moves = moves
+        .parse()
+        .map_err(|_| at(i)(format!("invalid moves {:?}", moves)))?;
+
+    let (i, top) = lines.next().ok_or("missing board")?;
+    if !is_wall(top) {
+        return Err(at(i)("expected a wall of `#` above the board".to_string()));
+    }
+    let width = top
+        .chars()
+        .count()
+        .checked_sub(2)
+        .ok_or("board too narrow")?;
+    let width = Coord::try_from(width).map_err(|_| "board too wide")?;
+
+    let mut height: Coord = 0;
+    let mut cells = Vec::new();
+    let mut head = None;
+    let mut apple = None;
+    let mut listed = None;
+    let mut closed = false;
+    for (i, line) in lines {
+        let at = at(i);
+        if closed {
+            let cells = line
+                .strip_prefix("body:")
+                .ok_or_else(|| at(format!("expected `body: x,y ...`, got {:?}", line)))?;
+            if listed.replace(parse_cells(cells).map_err(at)?).is_some() {
+                return Err(at("more than one `body` line".to_string()));
+            }
+            continue;
+        }
+        if is_wall(line) && line.chars().count() == width as usize + 2 {
+            closed = true;
+            continue;
+        }
+
+        let chars = line.chars().collect::<Vec<_>>();
+        if chars.len() != width as usize + 2 || chars[0] != '#' || chars[chars.len() - 1] != '#' {
+            return Err(at(format!("expected {} cells between walls", width)));
+        }
+        let y = height;
+        height = height.checked_add(1).ok_or("board too tall")?;
+        for (x, c) in chars[1..chars.len() - 1].iter().enumerate() {
+            let cell = Cell(x as Coord, y);
+            let heading = match c {
+                ' ' | '.' => continue,
+                '+' => {
+                    cells.push(cell);
+                    continue;
+                }
+                'O' if apple.is_none() => {
+                    apple = Some(cell);
+                    continue;
+                }
+                'O' => return Err(at("more than one apple".to_string())),
+                '^' => Heading::North,
+                'v' => Heading::South,
+                '>' => Heading::East,
+                '<' => Heading::West,
+                c => return Err(at(format!("unexpected {:?} at {},{}", c, x, y))),
+            };
+            if head.replace((cell, heading)).is_some() {
+                return Err(at("more than one head".to_string()));
+            }
+        }
+    }
+    if !closed {
+        return Err("expected a wall of `#` below the board".to_string());
+    }
+
+    let (head, heading) = head.ok_or("missing head")?;
+    let body = match listed {
+        Some(body) => {
+            let mut drawn = cells.clone();
+            drawn.push(head);
+            let mut sorted = body.clone();
+            drawn.sort_by_key(|c| (c.1, c.0));
+            sorted.sort_by_key(|c| (c.1, c.0));
+            if drawn != sorted {
+                return Err("`body` doesn't match the body on the board".to_string());
+            }
+            if body.last() != Some(&head) {
+                return Err("`body` doesn't end at the head".to_string());
+            }
+            body
+        }
+        None => trace(head, heading, cells)?,
+    };
+
+    let position = Position {
+        body,
+        heading,
+        apple: apple.ok_or("missing apple")?,
+        score,
+        moves,
+    };
+    Ok((width, height, position))
+}
+
+// Orders the body from the tail to the head by following `cells` back from the head.
+fn trace(head: Cell, heading: Heading, mut cells: Vec<Cell>) -> Result<Vec<Cell>, String> {
+    let mut body = vec![head];
+    if cells.is_empty() {
+        return Ok(body);
+    }
+
+    // The neck is the one cell whose place isn't a guess.
+    let neck = heading
+        .opposite()
+        .move_(head)
+        .filter(|c| cells.contains(c))
+        .ok_or_else(|| format!("no body behind the head at {},{}", head.0, head.1))?;
+    cells.retain(|c| *c != neck);
+    body.push(neck);
+
+    while !cells.is_empty() {
+        let last = *body.last().unwrap();
+        let next = cells
+            .iter()
+            .copied()
+            .filter(|c| c.taxicab_distance_to(last) == 1)
+            .collect::<Vec<_>>();
+        match next[..] {
+            [next] => {
+                cells.retain(|c| *c != next);
+                body.push(next);
+            }
+            [] => {
+                return Err(format!(
+                    "body ends at {},{} with {} cells left over; the drawing isn't one body",
+                    last.0,
+                    last.1,
+                    cells.len()
+                ))
+            }
+            _ => {
+                return Err(format!(
+                    "body is ambiguous after {},{}, which could go on to {}; list it with a \
+                     `body:` line",
+                    last.0,
+                    last.1,
+                    format_cells(&next)
+                ))
+            }
+        }
+    }
+
+    body.reverse();
+    Ok(body)
+}
+
+fn is_wall(line: &str) -> bool {
+    !line.is_empty() && line.chars().all(|c| c == '#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parses_the_documented_example() {
+        let text = "2/40\n######\n#  O #\n#++> #\n#+   #\n######";
+        let game = text.parse::<SnakeGame>().unwrap();
+        assert_eq!(game.body, [Cell(0, 2), Cell(0, 1), Cell(1, 1), Cell(2, 1)]);
+        assert_eq!((game.apple, game.heading), (Cell(2, 0), Heading::East));
+        assert_eq!((game.score, game.moves), (2, 40));
+        assert_eq!((game.width, game.height), (4, 3));
+        assert_eq!(game.to_string(), text);
+    }
+
+    #[test]
+    fn ambiguous_bodies_need_a_body_line() {
+        // From the neck at 1,0 the body could go on to 0,0 or 2,0.
+        let board = "0/0\n#####\n#+++#\n#+v+#\n#+++#\n#O  #\n#####";
+        let err = board.parse::<SnakeGame>().unwrap_err();
+        assert!(err.contains("ambiguous after 1,0"), "{}", err);
+
+        let text = format!("{}\nbody: 2,0 2,1 2,2 1,2 0,2 0,1 0,0 1,0 1,1", board);
+        let game = text.parse::<SnakeGame>().unwrap();
+        assert_eq!(game.body[0], Cell(2, 0));
+        assert_eq!(game.to_string(), text);
+
+        for body in [
+            "2,1 2,2 1,2 0,2 0,1 0,0 1,0 1,1",
+            "1,1 1,0 0,0 0,1 0,2 1,2 2,2 2,1 2,0",
+        ] {
+            let text = format!("{}\nbody: {}", board, body);
+            assert!(text.parse::<SnakeGame>().is_err(), "{}", body);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_boards() {
+        for (board, error) in [
+            ("####\n#>O#\n####", "score/moves"),
+            ("0/0\n####\n#> #\n####", "missing apple"),
+            ("0/0\n####\n#  O#\n####", "cells between walls"),
+            ("0/0\n####\n#>O#", "below the board"),
+            ("0/0\n####\n#> #\n#O #\n#< #\n####", "more than one head"),
+            ("0/0\n#####\n#+ >#\n#O  #\n#####", "no body behind the head"),
+            ("0/0\n#####\n#+>+#\n#O  #\n#####", "isn't one body"),
+            ("0/0\n####\n#>x#\n####", "unexpected 'x'"),
+        ] {
+            let err = board.parse::<SnakeGame>().unwrap_err();
+            assert!(err.contains(error), "{:?}: {}", board, err);
+        }
+    }
+
+    proptest! {
+        // Drawing any reachable game and reading it back gives the same position, and the
+        // drawing is unchanged.
+        #[test]
+        fn display_round_trip(
+            width in 2..10 as Coord,
+            height in 2..10 as Coord,
+            seed in any::<u64>(),
+            actions in prop::collection::vec(0..3usize, 0..200),
+        ) {
+            let rules = Rules { growth: 2, ..Default::default() };
+            let mut game = SnakeGame::seeded_with_rules(width, height, seed, rules).unwrap();
+            for action in actions {
+                let before = game.clone();
+                if game.do_action(Action::iter().nth(action).unwrap()).is_some() {
+                    game = before;
+                    break;
+                }
+            }
+
+            let text = game.to_string();
+            let parsed = text.parse::<SnakeGame>().unwrap();
+            prop_assert_eq!(parsed.position(), game.position());
+            prop_assert_eq!((parsed.width, parsed.height), (width, height));
+            prop_assert_eq!(parsed.to_string(), text);
+        }
+    }
+}
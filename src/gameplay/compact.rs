@@ -0,0 +1,239 @@
+use super::*;
+
+/// How many words `Compact` needs to hold any game on a `width` by `height` board: a bit per
+/// cell, plus two per body segment behind the head.
+pub const fn compact_words(width: Coord, height: Coord) -> usize {
+    let cells = width as usize * height as usize;
+    (cells + 2 * cells.saturating_sub(1)).div_ceil(64)
+}
+
+/// A game's position packed into a fixed number of words, for holding very many of them at once
+/// in searches: a copy is a `memcpy`, and equal positions hash equally.
+///
+/// Only what decides how the game goes on is kept: the body, heading, apple, score and growth.
+/// Moves aren't, so the same position reached by different routes compares equal, and neither
+/// are the rules or the apple generator, which `to_game` takes back.
+///
+/// `WORDS` has to be at least `compact_words` of the board to hold every game on it, and smaller
+/// values work for games with shorter bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Compact<const WORDS: usize> {
+    width: Coord,
+    height: Coord,
+    head: Cell,
+    apple: Cell,
+    heading: Heading,
+    length: u32,
+    growing: u32,
+    score: u32,
+    // A bit per cell, row by row, set under the body. Then two bits per segment behind the head,
+    // from the head back, with the heading from that segment into the one in front of it.
+    bits: [u64; WORDS],
+}
+
+const HEADINGS: [Heading; 4] = [Heading::North, Heading::South, Heading::East, Heading::West];
+
+impl<const WORDS: usize> Compact<WORDS> {
+    pub fn width(&self) -> Coord {
+        self.width
+    }
+
+    pub fn height(&self) -> Coord {
+        self.height
+    }
+
+    pub fn head(&self) -> Cell {
+        self.head
+    }
+
+    pub fn heading(&self) -> Heading {
+        self.heading
+    }
+
+    pub fn apple(&self) -> Cell {
+        self.apple
+    }
+
+    pub fn score(&self) -> usize {
+        self.score as usize
+    }
+
+    pub fn length(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn growing(&self) -> usize {
+        self.growing as usize
+    }
+
+    pub fn is_body(&self, cell: Cell) -> bool {
+        cell.0 < self.width && cell.1 < self.height && self.bit(self.index(cell))
+    }
+
+    /// From the head to the tail, the other way round from `SnakeGame::body`.
+    pub fn body(&self) -> impl Iterator<Item = Cell> + '_ {
+        let cells = self.width as usize * self.height as usize;
+        let mut segment = 0;
+        std::iter::successors(Some(self.head), move |&cell| {
+            let at = cells + 2 * segment;
+            segment += 1;
+            let code = self.bit(at) as usize | (self.bit(at + 1) as usize) << 1;
+            HEADINGS[code].opposite().move_(cell)
+        })
+        .take(self.length as usize)
+    }
+
+    /// The game this is a position of, with `rules` and later apples placed using `seed`.
+    pub fn to_game(&self, rules: Rules, seed: u64) -> SnakeGame {
+        let mut body = self.body().collect::<Vec<_>>();
+        body.reverse();
+        SnakeGame::from_snapshot(Snapshot {
+            width: self.width,
+            height: self.height,
+            score: self.score as usize,
+            moves: 0,
+            body,
+            heading: self.heading,
+            apple: self.apple,
+            growing: self.growing as usize,
+            rules,
+            spawn: Spawn::default(),
+            rng: rng::seeded(seed),
+        })
+        .expect("compact positions come from valid games")
+    }
+
+    fn index(&self, cell: Cell) -> usize {
+        cell.1 as usize * self.width as usize + cell.0 as usize
+    }
+
+    fn bit(&self, at: usize) -> bool {
+        self.bits[at / 64] >> (at % 64) & 1 == 1
+    }
+
+    fn set(&mut self, at: usize) {
+        self.bits[at / 64] |= 1 << (at % 64);
+    }
+}
+
+impl<const WORDS: usize> TryFrom<&SnakeGame> for Compact<WORDS> {
+    type Error = String;
+
+    fn try_from(game: &SnakeGame) -> Result<Self, Self::Error> {
+        let cells = game.width as usize * game.height as usize;
+        let needed = cells + 2 * (game.body.len() - 1);
+        if needed > 64 * WORDS {
+            return Err(format!(
+                "{} words can't hold a body of {} on a {}x{} board",
+                WORDS,
+                game.body.len(),
+                game.width,
+                game.height
+            ));
+        }
+        let count = |n: usize, what: &str| {
+            u32::try_from(n).map_err(|_| format!("{} {} doesn't fit in 32 bits", what, n))
+        };
+
+        let mut compact = Compact {
+            width: game.width,
+            height: game.height,
+            head: game.head(),
+            apple: game.apple,
+            heading: game.heading,
+            length: count(game.body.len(), "length")?,
+            growing: count(game.growing, "growth")?,
+            score: count(game.score, "score")?,
+            bits: [0; WORDS],
+        };
+        for cell in &game.body {
+            compact.set(compact.index(*cell));
+        }
+        let from_head = game.body.iter().rev();
+        for (segment, (front, cell)) in from_head.clone().zip(from_head.skip(1)).enumerate() {
+            let heading = cell
+                .heading_toward(*front)
+                .expect("body cells are distinct");
+            let code = HEADINGS.iter().position(|h| *h == heading).unwrap();
+            let at = cells + 2 * segment;
+            if code & 1 == 1 {
+                compact.set(at);
+            }
+            if code & 2 == 2 {
+                compact.set(at + 1);
+            }
+        }
+        Ok(compact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    type Compact10 = Compact<{ compact_words(10, 10) }>;
+
+    proptest! {
+        // Every position in a game packs and unpacks to the same position.
+        #[test]
+        fn round_trips(
+            seed in any::<u64>(),
+            actions in prop::collection::vec(0..3usize, 0..200),
+        ) {
+            let rules = Rules { tail_follow: true, growth: 2, starting_length: 3 };
+            let mut game = SnakeGame::seeded_with_rules(10, 10, seed, rules).unwrap();
+            for action in actions.into_iter().map(|a| Action::iter().nth(a).unwrap()) {
+                let compact = Compact10::try_from(&game).unwrap();
+                let unpacked = compact.to_game(rules, seed);
+                prop_assert_eq!(&unpacked.body, &game.body);
+                prop_assert_eq!(
+                    (unpacked.heading, unpacked.apple, unpacked.score, unpacked.growing),
+                    (game.heading, game.apple, game.score, game.growing)
+                );
+                prop_assert_eq!(Compact10::try_from(&unpacked), Ok(compact));
+                for cell in game.body.iter() {
+                    prop_assert!(compact.is_body(*cell));
+                }
+                prop_assert_eq!(
+                    (0..10).flat_map(|x| (0..10).map(move |y| Cell(x, y)))
+                        .filter(|c| compact.is_body(*c))
+                        .count(),
+                    game.body.len()
+                );
+                if game.do_action(action).is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(compact_words(10, 10), 5);
+        assert_eq!(compact_words(1, 1), 1);
+        assert!(std::mem::size_of::<Compact10>() <= 72);
+
+        let game = SnakeGame::seeded(10, 10, 0);
+        assert!(Compact::<1>::try_from(&game).is_err());
+        // Short bodies fit in less than a full board needs.
+        assert!(Compact::<2>::try_from(&game).is_ok());
+    }
+
+    #[test]
+    fn routes_to_the_same_position_are_equal() {
+        let mut game = "0/0\n######\n#    #\n# >  #\n#    #\n#   O#\n######"
+            .parse::<SnakeGame>()
+            .unwrap();
+        let mut seen = HashSet::new();
+        seen.insert(Compact10::try_from(&game).unwrap());
+        // Round a square and back to the start.
+        for _ in 0..4 {
+            assert_eq!(game.do_action(Action::TurnRight), None);
+            seen.insert(Compact10::try_from(&game).unwrap());
+        }
+        assert_eq!(game.moves, 4);
+        assert_eq!(seen.len(), 4);
+    }
+}
@@ -0,0 +1,68 @@
+use rand::{RngCore, SeedableRng};
+
+/// The generator games place apples with: xoshiro256++, the same as `rand`'s `SmallRng` on
+/// 64-bit targets. Unlike `SmallRng` it can be compared and, with the `serde` feature,
+/// serialized, which is what lets a `Snapshot` resume a game exactly.
+///
+/// Games seed it with `seeded` rather than its own `seed_from_u64`, which expands seeds
+/// differently.
+pub type GameRng = rand_xoshiro::Xoshiro256PlusPlus;
+
+/// The generator for `seed`, expanded through PCG32 as `SmallRng::seed_from_u64` does it, so
+/// seeds give the same games as before.
+pub(super) fn seeded(mut seed: u64) -> GameRng {
+    const MUL: u64 = 6364136223846793005;
+    const INC: u64 = 11634580027462260723;
+    let mut bytes = [0; 32];
+    for chunk in bytes.chunks_exact_mut(4) {
+        seed = seed.wrapping_mul(MUL).wrapping_add(INC);
+        let xorshifted = (((seed >> 18) ^ seed) >> 27) as u32;
+        chunk.copy_from_slice(&xorshifted.rotate_right((seed >> 59) as u32).to_le_bytes());
+    }
+    GameRng::from_seed(bytes)
+}
+
+// Whether `rng` is in the all-zero state, where it only ever returns 0. Seeding never gets there,
+// but a hand-edited snapshot can. Every other state moves on with each draw.
+pub(super) fn is_stuck(rng: &GameRng) -> bool {
+    let mut next = rng.clone();
+    next.next_u64();
+    next == *rng
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::SmallRng, Rng};
+
+    // Seeded games only replay the same as before if this matches `SmallRng` draw for draw.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn matches_small_rng() {
+        for seed in [0, 1, 42, u64::MAX] {
+            let mut ours = seeded(seed);
+            let mut theirs = SmallRng::seed_from_u64(seed);
+            for n in 1..200u32 {
+                assert_eq!(ours.gen_range(0..n), theirs.gen_range(0..n));
+                assert_eq!(ours.next_u64(), theirs.next_u64());
+            }
+            let (mut a, mut b) = ([0; 13], [0; 13]);
+            ours.fill_bytes(&mut a);
+            theirs.fill_bytes(&mut b);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn seeded_generators_arent_stuck() {
+        assert!(!is_stuck(&seeded(0)));
+        assert!(!is_stuck(&GameRng::from_seed([0; 32])));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn zero_state_is_stuck() {
+        let rng: GameRng = serde_json::from_str(r#"{"s":[0,0,0,0]}"#).unwrap();
+        assert!(is_stuck(&rng));
+    }
+}
@@ -0,0 +1,171 @@
+use super::*;
+
+/// Everything in a `SnakeGame` as plain data, including the apple generator's state, so a game
+/// saved and loaded again goes on exactly as it would have. With the `serde` feature this is the
+/// form `SnakeGame` is serialized in, and its fields are the stable format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub width: Coord,
+    pub height: Coord,
+    pub score: usize,
+    pub moves: usize,
+    /// From the tail to the head.
+    pub body: Vec<Cell>,
+    pub heading: Heading,
+    pub apple: Cell,
+    pub growing: usize,
+    pub rules: Rules,
+    pub spawn: Spawn,
+    /// Places later apples. Serialized as its four words of state.
+    pub rng: GameRng,
+}
+
+impl SnakeGame {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            width: self.width,
+            height: self.height,
+            score: self.score,
+            moves: self.moves,
+            body: self.body.iter().copied().collect(),
+            heading: self.heading,
+            apple: self.apple,
+            growing: self.growing,
+            rules: self.rules,
+            spawn: self.spawn,
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// The game `snapshot` was taken of, checked the same way as `from_position`.
+    pub fn from_snapshot(snapshot: Snapshot) -> Result<SnakeGame, String> {
+        // A game that ended by turning into something has a heading that doesn't follow the
+        // body, so the check is only that it isn't back into the neck.
+        let heading = match snapshot.body[..] {
+            [.., neck, head] => {
+                let forward = neck.heading_toward(head).unwrap_or(snapshot.heading);
+                if snapshot.heading == forward.opposite() {
+                    return Err("heading points back into the body".to_string());
+                }
+                forward
+            }
+            _ => snapshot.heading,
+        };
+        let position = Position {
+            body: snapshot.body,
+            heading,
+            apple: snapshot.apple,
+            score: snapshot.score,
+            moves: snapshot.moves,
+        };
+
+        let mut game =
+            SnakeGame::from_position(snapshot.width, snapshot.height, 0, snapshot.rules, position)?;
+        game.heading = snapshot.heading;
+        game.growing = snapshot.growing;
+        game.spawn = snapshot.spawn;
+        if rng::is_stuck(&snapshot.rng) {
+            return Err("generator state can't be all zeros".to_string());
+        }
+        game.rng = snapshot.rng;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        // A game restored from a snapshot at any point plays out the same as the original,
+        // apples included.
+        #[test]
+        fn resumes_identically(
+            seed in any::<u64>(),
+            split in 0usize..150,
+            actions in prop::collection::vec(0..3usize, 0..300),
+        ) {
+            let rules = Rules { tail_follow: true, growth: 2, starting_length: 3 };
+            let spawn = Spawn { shape: Shape::Random, wall_margin: 0 };
+            let mut game = SnakeGame::seeded_with_spawn(8, 6, seed, rules, spawn).unwrap();
+            let actions = actions.into_iter().map(|a| Action::iter().nth(a).unwrap());
+
+            let mut resumed = None;
+            for (i, action) in actions.enumerate() {
+                if i == split {
+                    resumed = Some(SnakeGame::from_snapshot(game.snapshot()).unwrap());
+                }
+                let outcome = game.do_action(action);
+                if let Some(resumed) = &mut resumed {
+                    prop_assert_eq!(resumed.do_action(action), outcome);
+                    prop_assert_eq!(&*resumed, &game);
+                }
+                if outcome.is_some() {
+                    break;
+                }
+            }
+            // Games that have ended load too.
+            prop_assert_eq!(SnakeGame::from_snapshot(game.snapshot()), Ok(game));
+        }
+    }
+
+    // A game read back from JSON goes on exactly like the original, apples included.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip_keeps_playing() {
+        use serde_json::{json, Value};
+
+        let rules = Rules {
+            starting_length: 3,
+            ..Default::default()
+        };
+        let mut game = SnakeGame::seeded_with_rules(10, 10, 5, rules).unwrap();
+
+        let text = serde_json::to_string(&game).unwrap();
+        let mut loaded = serde_json::from_str::<SnakeGame>(&text).unwrap();
+        assert_eq!(loaded, game);
+        // Heads for each apple, so the generator places several after the round trip.
+        for _ in 0..200 {
+            let action = Action::iter()
+                .filter_map(|a| Some((a, game.do_many([a]).ok()?)))
+                .min_by_key(|(_, next)| next.head().taxicab_distance_to(game.apple))
+                .map_or(Action::GoStraight, |(a, _)| a);
+            let outcome = game.do_action(action);
+            assert_eq!(loaded.do_action(action), outcome);
+            assert_eq!(loaded, game);
+            if outcome.is_some() {
+                break;
+            }
+        }
+        assert!(game.score >= 3, "{}", game.score);
+
+        // Snapshots that don't make a valid game are rejected while deserializing.
+        let value = serde_json::to_value(&loaded).unwrap();
+        assert_eq!(value["rng"]["s"].as_array().map(Vec::len), Some(4));
+        let edited = |key: &str, to: Value| {
+            let mut value = value.clone();
+            value[key] = to;
+            serde_json::from_value::<SnakeGame>(value)
+        };
+        assert!(edited("rng", json!({ "s": [0, 0, 0, 0] })).is_err());
+        assert!(edited("body", json!([])).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn gameplay_types_are_serializable() {
+        fn serializable<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+        serializable::<SnakeGame>();
+        serializable::<Snapshot>();
+        serializable::<Position>();
+        serializable::<Cell>();
+        serializable::<Action>();
+        serializable::<Heading>();
+        serializable::<Terminal>();
+        serializable::<Occupant>();
+        serializable::<Rules>();
+        serializable::<Spawn>();
+    }
+}
@@ -0,0 +1,401 @@
+use super::*;
+
+/// How the snake is placed at the start of a game. Its length comes from `Rules::starting_length`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spawn {
+    pub shape: Shape,
+    /// Minimum number of cells between the head and every wall, so the first moves are safe.
+    pub wall_margin: Coord,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    /// A straight line behind the head.
+    #[default]
+    Straight,
+    /// Folded back and forth behind the head in a strip about as wide as it is long.
+    Coiled,
+    /// A random self-avoiding walk behind the head.
+    Random,
+}
+
+/// Comma-separated, e.g. `shape=coiled,margin=2`. Options left out keep their default.
+impl FromStr for Spawn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spawn = Spawn::default();
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            match option.split_once('=') {
+                Some(("shape", "straight")) => spawn.shape = Shape::Straight,
+                Some(("shape", "coiled")) => spawn.shape = Shape::Coiled,
+                Some(("shape", "random")) => spawn.shape = Shape::Random,
+                Some(("margin", value)) => {
+                    spawn.wall_margin = value
+                        .parse()
+                        .map_err(|_| format!("invalid number in {:?}", option))?
+                }
+                _ => return Err(format!("unknown spawn option {:?}", option)),
+            }
+        }
+        Ok(spawn)
+    }
+}
+
+/// The inverse of `from_str`, listing only options that differ from the default.
+impl fmt::Display for Spawn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut options = Vec::new();
+        match self.shape {
+            Shape::Straight => {}
+            Shape::Coiled => options.push("shape=coiled".to_string()),
+            Shape::Random => options.push("shape=random".to_string()),
+        }
+        if self.wall_margin != 0 {
+            options.push(format!("margin={}", self.wall_margin));
+        }
+        write!(f, "{}", options.join(","))
+    }
+}
+
+/// The parts of a game that make up a position on the board, for starting mid-game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// From the tail to the head, like `SnakeGame::body`.
+    pub body: Vec<Cell>,
+    pub heading: Heading,
+    pub apple: Cell,
+    pub score: usize,
+    pub moves: usize,
+}
+
+impl SnakeGame {
+    /// Fails if no placement of the starting body fits on the board, such as a body longer than
+    /// the board is wide and high, or a margin that leaves no cells for the head.
+    pub fn seeded_with_spawn(
+        width: Coord,
+        height: Coord,
+        seed: u64,
+        rules: Rules,
+        spawn: Spawn,
+    ) -> Result<SnakeGame, String> {
+        SnakeGame::with_rng(width, height, rules, spawn, rng::seeded(seed))
+    }
+
+    /// Starts from `position` instead of spawning, with later apples placed using `seed`.
+    pub fn from_position(
+        width: Coord,
+        height: Coord,
+        seed: u64,
+        rules: Rules,
+        position: Position,
+    ) -> Result<SnakeGame, String> {
+        let in_bounds = |c: Cell| c.0 < width && c.1 < height;
+        let body = &position.body;
+
+        let head = *body.last().ok_or("empty body")?;
+        if let Some(c) = body.iter().find(|c| !in_bounds(**c)) {
+            return Err(format!("body cell {:?} is off the board", c));
+        }
+        // Once the game is won the body fills the board and the last apple is under it.
+        let full = body.len() == width as usize * height as usize;
+        if !in_bounds(position.apple) || (body.contains(&position.apple) && !full) {
+            return Err(format!(
+                "apple {:?} is off the board or on the body",
+                position.apple
+            ));
+        }
+        if let Some((i, _)) = body
+            .iter()
+            .enumerate()
+            .find(|(i, c)| body[i + 1..].contains(c))
+        {
+            return Err(format!("body cell {:?} is repeated", body[i]));
+        }
+        if body.windows(2).any(|p| p[0].taxicab_distance_to(p[1]) != 1) {
+            return Err("body is not connected".to_string());
+        }
+        if let [.., neck, _] = body[..] {
+            if neck.heading_toward(head) != Some(position.heading) {
+                return Err("heading points back into the body".to_string());
+            }
+        }
+
+        Ok(SnakeGame {
+            score: position.score,
+            moves: position.moves,
+            width,
+            height,
+            apple: position.apple,
+            body: position.body.into(),
+            heading: position.heading,
+            rules,
+            spawn: Spawn::default(),
+            growing: 0,
+            rng: rng::seeded(seed),
+        })
+    }
+
+    pub fn position(&self) -> Position {
+        Position {
+            body: self.body.iter().copied().collect(),
+            heading: self.heading,
+            apple: self.apple,
+            score: self.score,
+            moves: self.moves,
+        }
+    }
+
+    // Picks a head and heading with the head clear of the walls and room for the whole starting
+    // body behind it. Random walks that keep running into themselves fall back to the coiled and
+    // then the straight shape, so placing only fails if none of them fit.
+    pub(super) fn place_body(&mut self, spawn: Spawn) -> Result<(), String> {
+        let margin = spawn.wall_margin;
+        let heads = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| Cell(col, row)))
+            .filter(|c| {
+                c.0 >= margin
+                    && c.1 >= margin
+                    && c.0 as usize + (margin as usize) < self.width as usize
+                    && c.1 as usize + (margin as usize) < self.height as usize
+            })
+            .flat_map(|head| Heading::iter().map(move |heading| (head, heading)))
+            .collect::<Vec<_>>();
+
+        let straight = |game: &mut SnakeGame| {
+            let placements = heads
+                .iter()
+                .filter_map(|&(head, heading)| Some((game.line(head, heading)?, heading)))
+                .collect::<Vec<_>>();
+            placements.choose(&mut game.rng).cloned()
+        };
+        let coiled = |game: &mut SnakeGame| {
+            let placements = heads
+                .iter()
+                .flat_map(|&(head, heading)| {
+                    [Action::TurnLeft, Action::TurnRight].map(|side| (head, heading, side))
+                })
+                .filter_map(|(head, heading, side)| {
+                    Some((game.coil(head, heading, side)?, heading))
+                })
+                .collect::<Vec<_>>();
+            placements.choose(&mut game.rng).cloned()
+        };
+
+        let placement = match spawn.shape {
+            Shape::Straight => straight(self),
+            Shape::Coiled => coiled(self),
+            Shape::Random => (0..100)
+                .find_map(|_| {
+                    let (head, heading) = *heads.choose(&mut self.rng)?;
+                    Some((self.walk(head, heading)?, heading))
+                })
+                .or_else(|| coiled(self))
+                .or_else(|| straight(self)),
+        };
+
+        let (cells, heading) = placement.ok_or_else(|| {
+            format!(
+                "no room for a snake of length {} on a {}x{} board with a margin of {}",
+                self.rules.starting_length, self.width, self.height, margin
+            )
+        })?;
+        self.body = cells.into_iter().rev().collect();
+        self.heading = heading;
+        Ok(())
+    }
+
+    // Whether `cell` can be part of a body being placed after `cells`. The apple is placed after
+    // the body, so it's not in the way.
+    fn fits(&self, cells: &[Cell], cell: Cell) -> bool {
+        !cells.contains(&cell)
+    }
+
+    fn line(&self, head: Cell, heading: Heading) -> Option<Vec<Cell>> {
+        let mut cells = vec![head];
+        while cells.len() < self.rules.starting_length {
+            let next = self.cell_delta(*cells.last().unwrap(), heading.opposite())?;
+            cells.push(next);
+        }
+        Some(cells)
+    }
+
+    // Runs back from the head, then shifts one cell toward `side` and runs the other way.
+    fn coil(&self, head: Cell, heading: Heading, mut side: Action) -> Option<Vec<Cell>> {
+        let length = self.rules.starting_length;
+        let run = (length as f32).sqrt().ceil() as usize;
+        let mut direction = heading.opposite();
+        let mut cells = vec![head];
+        let mut straight = 1;
+        while cells.len() < length {
+            let step = if straight < run {
+                straight += 1;
+                direction
+            } else {
+                let shift = direction.after(side);
+                straight = 1;
+                direction = direction.opposite();
+                side = match side {
+                    Action::TurnLeft => Action::TurnRight,
+                    _ => Action::TurnLeft,
+                };
+                shift
+            };
+            let next = self.cell_delta(*cells.last().unwrap(), step)?;
+            if !self.fits(&cells, next) {
+                return None;
+            }
+            cells.push(next);
+        }
+        Some(cells)
+    }
+
+    fn walk(&mut self, head: Cell, heading: Heading) -> Option<Vec<Cell>> {
+        let mut cells = vec![head];
+        // The neck has to be behind the head so the first move can't reverse into it.
+        if self.rules.starting_length > 1 {
+            let neck = self.cell_delta(head, heading.opposite())?;
+            if !self.fits(&cells, neck) {
+                return None;
+            }
+            cells.push(neck);
+        }
+        while cells.len() < self.rules.starting_length {
+            let last = *cells.last().unwrap();
+            let options = Heading::iter()
+                .filter_map(|h| self.cell_delta(last, h))
+                .filter(|c| self.fits(&cells, *c))
+                .collect::<Vec<_>>();
+            cells.push(*options.choose(&mut self.rng)?);
+        }
+        Some(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn spawn() -> impl Strategy<Value = Spawn> {
+        let shape = prop::sample::select(&[Shape::Straight, Shape::Coiled, Shape::Random][..]);
+        (shape, 0..3 as Coord).prop_map(|(shape, wall_margin)| Spawn { shape, wall_margin })
+    }
+
+    proptest! {
+        #[test]
+        fn spawned_bodies_are_valid(
+            seed in any::<u64>(),
+            spawn in spawn(),
+            starting_length in 1usize..12,
+        ) {
+            let rules = Rules {
+                starting_length,
+                ..Default::default()
+            };
+            let game = match SnakeGame::seeded_with_spawn(10, 10, seed, rules, spawn) {
+                Ok(game) => game,
+                Err(e) => {
+                    // Only a straight body can be too long for what the margin leaves of the
+                    // board, since the other shapes fold up.
+                    let room = 10 - spawn.wall_margin as usize;
+                    prop_assert!(spawn.shape == Shape::Straight && starting_length > room, "{}", e);
+                    return Ok(());
+                }
+            };
+            prop_assert_eq!(game.body.len(), starting_length);
+
+            // `from_position` rejects disconnected bodies, overlaps and bad headings.
+            let position = game.position();
+            let resumed = SnakeGame::from_position(10, 10, seed, rules, position.clone());
+            prop_assert_eq!(resumed.map(|g| g.position()), Ok(position));
+
+            let head = game.head();
+            let margin = spawn.wall_margin;
+            prop_assert!(head.0 >= margin && head.0 + margin < 10);
+            prop_assert!(head.1 >= margin && head.1 + margin < 10);
+        }
+
+        #[test]
+        fn spawn_round_trip(spawn in spawn()) {
+            prop_assert_eq!(spawn.to_string().parse::<Spawn>(), Ok(spawn));
+        }
+    }
+
+    #[test]
+    fn coil_folds_behind_head() {
+        let mut game = SnakeGame::seeded(5, 5, 0);
+        game.rules.starting_length = 9;
+        let coil = game
+            .coil(Cell(3, 1), Heading::East, Action::TurnLeft)
+            .unwrap();
+        assert_eq!(
+            coil,
+            [
+                Cell(3, 1),
+                Cell(2, 1),
+                Cell(1, 1),
+                Cell(1, 2),
+                Cell(2, 2),
+                Cell(3, 2),
+                Cell(3, 3),
+                Cell(2, 3),
+                Cell(1, 3),
+            ]
+        );
+        // Folding the other way runs into the top wall.
+        assert_eq!(
+            game.coil(Cell(3, 1), Heading::East, Action::TurnRight),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_positions() {
+        let position = |body: &[Cell], heading, apple| Position {
+            body: body.to_vec(),
+            heading,
+            apple,
+            score: 0,
+            moves: 0,
+        };
+        let load = |p| SnakeGame::from_position(4, 4, 0, Rules::default(), p);
+
+        assert!(load(position(
+            &[Cell(0, 0), Cell(1, 0)],
+            Heading::East,
+            Cell(3, 3)
+        ))
+        .is_ok());
+        assert!(load(position(&[], Heading::East, Cell(3, 3))).is_err());
+        assert!(load(position(
+            &[Cell(0, 0), Cell(1, 0)],
+            Heading::West,
+            Cell(3, 3)
+        ))
+        .is_err());
+        assert!(load(position(
+            &[Cell(0, 0), Cell(2, 0)],
+            Heading::East,
+            Cell(3, 3)
+        ))
+        .is_err());
+        assert!(load(position(
+            &[Cell(0, 0), Cell(1, 0)],
+            Heading::East,
+            Cell(1, 0)
+        ))
+        .is_err());
+        assert!(load(position(
+            &[Cell(3, 0), Cell(4, 0)],
+            Heading::East,
+            Cell(3, 3)
+        ))
+        .is_err());
+        let looped = [Cell(0, 0), Cell(1, 0), Cell(1, 1), Cell(0, 1), Cell(0, 0)];
+        assert!(load(position(&looped, Heading::North, Cell(3, 3))).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{
+    agents::{heuristic, Agent},
+    gameplay::*,
+    render,
+};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// A human-controlled game driven by the keyboard in raw mode.
+pub struct Interactive {
+    pub width: Coord,
+    pub height: Coord,
+    pub tick: Duration,
+    pub high_scores: PathBuf,
+}
+
+impl Default for Interactive {
+    fn default() -> Self {
+        Interactive {
+            width: 10,
+            height: 10,
+            tick: Duration::from_millis(150),
+            high_scores: PathBuf::from("high_scores.txt"),
+        }
+    }
+}
+
+enum Command {
+    Steer(Heading),
+    TogglePause,
+    Restart,
+    Quit,
+}
+
+fn command(key: KeyEvent) -> Option<Command> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    Some(match key.code {
+        KeyCode::Up | KeyCode::Char('w') => Command::Steer(Heading::North),
+        KeyCode::Down | KeyCode::Char('s') => Command::Steer(Heading::South),
+        KeyCode::Right | KeyCode::Char('d') => Command::Steer(Heading::East),
+        KeyCode::Left | KeyCode::Char('a') => Command::Steer(Heading::West),
+        KeyCode::Char('p') | KeyCode::Char(' ') => Command::TogglePause,
+        KeyCode::Char('r') => Command::Restart,
+        KeyCode::Char('q') | KeyCode::Esc => Command::Quit,
+        _ => return None,
+    })
+}
+
+impl Interactive {
+    pub fn play(&self) -> io::Result<()> {
+        self.play_assisted(None)
+    }
+
+    /// Plays with `assistant` suggesting a move and showing its plan every tick.
+    pub fn play_assisted(&self, assistant: Option<&mut dyn Agent>) -> io::Result<()> {
+        let _screen = render::FullScreen::enter()?;
+        self.run(assistant.map(Assist::new))
+    }
+
+    fn run(&self, mut assist: Option<Assist>) -> io::Result<()> {
+        let mut high_scores = HighScores::load(&self.high_scores)?;
+        let mut screen = render::Terminal::default();
+
+        let mut game = SnakeGame::random(self.width, self.height);
+        // Turns pressed faster than the tick rate are queued so quick double turns aren't lost.
+        let mut steering = VecDeque::new();
+        let mut paused = false;
+        let mut outcome = None;
+
+        loop {
+            if let (Some(assist), None) = (&mut assist, outcome) {
+                assist.suggest(&game);
+            }
+
+            let best = high_scores.get(self.width, self.height);
+            let status = match (outcome, paused) {
+                (Some(terminal), _) => format!(
+                    "{:?} with {}. Best {}. r: restart, q: quit",
+                    terminal, game.score, best
+                ),
+                (None, true) => format!("Paused. Best {}. p: resume, r: restart, q: quit", best),
+                (None, false) => format!("Best {}. arrows/WASD: steer, p: pause", best),
+            };
+            let draw = |screen: &mut render::Terminal, steering: &VecDeque<Heading>| {
+                let frame = match (&assist, outcome) {
+                    (Some(assist), None) => {
+                        assist.frame(&game, pending_action(&game, steering), &status)
+                    }
+                    _ => format!("{}\n{}", render::dbg_string(&game), status),
+                };
+                screen.draw(&frame)
+            };
+            draw(&mut screen, &steering)?;
+
+            let deadline = Instant::now() + self.tick;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                if !event::poll(timeout)? {
+                    break;
+                }
+                let key = match event::read()? {
+                    Event::Key(key) => key,
+                    _ => continue,
+                };
+                match command(key) {
+                    Some(Command::Steer(heading)) if outcome.is_none() && !paused => {
+                        steering.push_back(heading);
+                        draw(&mut screen, &steering)?;
+                    }
+                    Some(Command::TogglePause) if outcome.is_none() => {
+                        paused = !paused;
+                        break;
+                    }
+                    Some(Command::Restart) => {
+                        game = SnakeGame::random(self.width, self.height);
+                        steering.clear();
+                        paused = false;
+                        outcome = None;
+                        if let Some(assist) = &mut assist {
+                            assist.forget();
+                        }
+                        break;
+                    }
+                    Some(Command::Quit) => return Ok(()),
+                    _ => {}
+                }
+            }
+
+            if paused || outcome.is_some() || Instant::now() < deadline {
+                continue;
+            }
+
+            let action = pending_action(&game, &steering);
+            while let Some(heading) = steering.pop_front() {
+                if game.heading.turn_towards(heading).is_some() {
+                    break;
+                }
+            }
+            if let Some(assist) = &mut assist {
+                assist.followed(action);
+            }
+            if let Some(terminal) = game.do_action(action) {
+                outcome = Some(terminal);
+                if high_scores.record(self.width, self.height, game.score) {
+                    high_scores.save(&self.high_scores)?;
+                }
+            }
+        }
+    }
+}
+
+// The action the next tick will take: the first queued turn that is legal, else straight ahead.
+// Reversing into the body isn't a legal action, so those presses are skipped.
+fn pending_action(game: &SnakeGame, steering: &VecDeque<Heading>) -> Action {
+    steering
+        .iter()
+        .find_map(|heading| game.heading.turn_towards(*heading))
+        .unwrap_or(Action::GoStraight)
+}
+
+struct Assist<'a> {
+    agent: &'a mut dyn Agent,
+    suggestion: Option<(Action, Vec<Action>)>,
+}
+
+impl<'a> Assist<'a> {
+    fn new(agent: &'a mut dyn Agent) -> Self {
+        Assist {
+            agent,
+            suggestion: None,
+        }
+    }
+
+    fn suggest(&mut self, game: &SnakeGame) {
+        if self.suggestion.is_none() {
+            let action = self.agent.action(game);
+            let plan = self.agent.plan().unwrap_or_default();
+            self.suggestion = Some((action, plan));
+        }
+    }
+
+    // Keeps the agent's plan only while the human follows it.
+    fn followed(&mut self, action: Action) {
+        match self.suggestion.take() {
+            Some((suggested, _)) if suggested == action => {}
+            _ => self.agent.reset(),
+        }
+    }
+
+    fn forget(&mut self) {
+        self.agent.reset();
+        self.suggestion = None;
+    }
+
+    fn frame(&self, game: &SnakeGame, pending: Action, status: &str) -> String {
+        let (suggested, plan) = match &self.suggestion {
+            Some((suggested, plan)) => (*suggested, plan.as_slice()),
+            None => return format!("{}\n{}", render::dbg_string(game), status),
+        };
+        let path = [suggested].iter().chain(plan).copied().collect::<Vec<_>>();
+
+        let warning = match game.do_many([pending]) {
+            Err(Terminal::Died) => format!("Warning: {:?} dies", pending),
+            Ok(next) if !heuristic::can_reach_tail(&next) => {
+                format!("Warning: {:?} cuts you off from your tail", pending)
+            }
+            _ => String::new(),
+        };
+
+        format!(
+            "{}\n{}\nAgent suggests {:?}\n{}",
+            render::dbg_string_with_path(game, &render::path_cells(game, &path)),
+            status,
+            suggested,
+            warning
+        )
+    }
+}
+
+/// Best score per board size, stored one `<width>x<height> <score>` line per size.
+#[derive(Debug, Default)]
+pub struct HighScores {
+    scores: Vec<((Coord, Coord), usize)>,
+}
+
+impl HighScores {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HighScores::default()),
+            Err(e) => return Err(e),
+        };
+
+        let scores = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let parsed = line.split_once(' ').and_then(|(size, score)| {
+                    let (w, h) = size.split_once('x')?;
+                    Some((
+                        (w.parse().ok()?, h.parse().ok()?),
+                        score.trim().parse().ok()?,
+                    ))
+                });
+                parsed.ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid high score line {:?}", line),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(HighScores { scores })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents = self
+            .scores
+            .iter()
+            .map(|((w, h), score)| format!("{}x{} {}\n", w, h, score))
+            .collect::<String>();
+        std::fs::write(path, contents)
+    }
+
+    pub fn get(&self, width: Coord, height: Coord) -> usize {
+        self.scores
+            .iter()
+            .find(|(size, _)| *size == (width, height))
+            .map_or(0, |(_, score)| *score)
+    }
+
+    /// Returns whether `score` is a new high score.
+    pub fn record(&mut self, width: Coord, height: Coord, score: usize) -> bool {
+        if score <= self.get(width, height) {
+            return false;
+        }
+        self.scores.retain(|(size, _)| *size != (width, height));
+        self.scores.push(((width, height), score));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn records_only_new_bests_per_size() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.get(10, 10), 0);
+        assert!(!scores.record(10, 10, 0));
+        assert!(scores.record(10, 10, 5));
+        assert!(!scores.record(10, 10, 5));
+        assert!(!scores.record(10, 10, 3));
+        assert!(scores.record(6, 8, 2));
+        assert!(scores.record(10, 10, 7));
+        assert_eq!(
+            (scores.get(10, 10), scores.get(6, 8), scores.get(8, 6)),
+            (7, 2, 0)
+        );
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let path = temp_path("high-scores");
+        assert_eq!(HighScores::load(&path).unwrap().get(10, 10), 0);
+
+        let mut scores = HighScores::default();
+        scores.record(10, 10, 12);
+        scores.record(6, 6, 4);
+        scores.save(&path).unwrap();
+        let loaded = HighScores::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.scores, scores.scores);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let path = temp_path("bad-high-scores");
+        for contents in ["10x10", "10 5", "10xten 5", "10x10 -1"] {
+            std::fs::write(&path, format!("6x6 4\n\n{}\n", contents)).unwrap();
+            let err = HighScores::load(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", contents);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
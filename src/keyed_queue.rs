@@ -23,6 +23,15 @@ where
     }
 }
 
+impl<K, V> Default for KeyedQueue<K, V>
+where
+    K: Ord + Eq,
+{
+    fn default() -> Self {
+        KeyedQueue::new()
+    }
+}
+
 struct Keyed<K, V> {
     key: K,
     value: V,
@@ -0,0 +1,39 @@
+//! Snake, with agents that play it and ways to watch them.
+//!
+//! The engine in `gameplay` only needs `rand`. Agents and the scenario suite are behind the
+//! `agents` feature, output behind `render`, and the runner and terminal UIs need both. `trace`
+//! adds `tracing` spans to the game loop and the searches, see `profile`.
+
+pub mod gameplay;
+pub use gameplay::*;
+
+pub mod keyed_queue;
+pub use keyed_queue::*;
+
+pub mod profile;
+
+pub mod recording;
+
+pub mod tree_search;
+pub use tree_search::*;
+
+#[cfg(feature = "agents")]
+pub mod agents;
+
+#[cfg(feature = "agents")]
+pub mod scenario;
+
+#[cfg(feature = "agents")]
+pub mod minimize;
+
+#[cfg(feature = "render")]
+pub mod render;
+
+#[cfg(all(feature = "agents", feature = "render"))]
+pub mod dashboard;
+
+#[cfg(all(feature = "agents", feature = "render"))]
+pub mod interactive;
+
+#[cfg(all(feature = "agents", feature = "render"))]
+pub mod runner;
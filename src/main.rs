@@ -1,35 +1,576 @@
-mod agents;
-use agents::*;
+use snake::{
+    agents::{self, registry::AgentConfig, Agent},
+    dashboard, interactive, minimize, recording,
+    render::{self, Renderer},
+    runner::Runner,
+    scenario, Coord, Rules, SnakeGame, Spawn,
+};
 
-mod gameplay;
-use gameplay::*;
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut args = args.iter().map(String::as_str).collect::<Vec<_>>();
 
-mod keyed_queue;
-pub use keyed_queue::*;
+    let mut output = Output::default();
+    let mut flag = |name: &str| {
+        let i = args.iter().position(|a| *a == name)?;
+        args.remove(i);
+        if i == args.len() {
+            eprintln!("{} needs a value", name);
+            usage();
+        }
+        Some(args.remove(i).to_string())
+    };
+    output.gif = flag("--gif");
+    output.record = flag("--record");
+    output.stats = flag("--stats");
+    let setup = Setup {
+        rules: flag("--rules")
+            .map(|rules| rules.parse().expect("invalid rules"))
+            .unwrap_or_default(),
+        spawn: flag("--spawn")
+            .map(|spawn| spawn.parse().expect("invalid spawn"))
+            .unwrap_or_default(),
+        from: flag("--from"),
+    };
+    #[cfg(feature = "trace")]
+    let _trace = flag("--trace").map(|path| trace(&path));
+    if let Some(addr) = flag("--web") {
+        output.display = Display::Web(addr);
+    }
+    if let Some(i) = args.iter().position(|a| *a == "--color") {
+        args.remove(i);
+        output.display = Display::Color;
+    }
 
-mod render;
-use render::*;
+    match args[..] {
+        [] => watch(
+            search_agent("average-path").as_mut(),
+            "average-path",
+            &setup,
+            &output,
+        ),
+        ["tree-search"] => watch(
+            search_agent("tree-search").as_mut(),
+            "tree-search",
+            &setup,
+            &output,
+        ),
+        ["watch", agent] => watch(search_agent(agent).as_mut(), agent, &setup, &output),
+        ["agents"] => list_agents(),
+        ["train-q" | "train-sarsa", path] => train_q(args[0], path, 10_000),
+        ["train-q" | "train-sarsa", path, episodes] => {
+            train_q(args[0], path, episodes.parse().expect("invalid episodes"))
+        }
+        ["q", path] => watch(
+            &mut agents::q_learning::QLearning::load(path).expect("failed to load table"),
+            "q-learning",
+            &setup,
+            &output,
+        ),
+        ["evolve", path] => evolve(path, 100),
+        ["evolve", path, generations] => {
+            evolve(path, generations.parse().expect("invalid generations"))
+        }
+        ["neural", path] => watch(
+            &mut agents::neural::Neural {
+                genome: agents::neural::Genome::load(path).expect("failed to load genome"),
+            },
+            "neural",
+            &setup,
+            &output,
+        ),
+        ["play"] => play(10, 10),
+        ["play", width, height] => play(
+            width.parse().expect("invalid width"),
+            height.parse().expect("invalid height"),
+        ),
+        ["assist"] => assist(search_agent("average-path").as_mut()),
+        ["assist", agent] => assist(search_agent(agent).as_mut()),
+        ["dashboard", ref agents @ ..] if (2..=4).contains(&agents.len()) => dashboard(agents),
+        ["record", path] => record(path, "average-path", &setup),
+        ["record", path, agent] => record(path, agent, &setup),
+        ["export", recording, out] => export(recording, out, None),
+        ["export", recording, out, moves] => {
+            export(recording, out, Some(moves.parse().expect("invalid moves")))
+        }
+        ["compare", a, b] => compare(a, b, None, None),
+        ["compare", a, b, games] => compare(a, b, Some(games), None),
+        ["compare", a, b, games, size] => compare(a, b, Some(games), Some(size)),
+        ["profile", agent] => profile(agent, None, None, &setup),
+        ["profile", agent, games] => profile(agent, Some(games), None, &setup),
+        ["profile", agent, games, size] => profile(agent, Some(games), Some(size), &setup),
+        ["sweep", spec] => sweep(spec, None),
+        ["sweep", spec, csv] => sweep(spec, Some(csv)),
+        ["minimize", recording, agent, out] => minimize(recording, agent, out, None),
+        ["minimize", recording, agent, out, horizon] => minimize(
+            recording,
+            agent,
+            out,
+            Some(horizon.parse().expect("invalid horizon")),
+        ),
+        ["scenarios"] => scenarios("scenarios"),
+        ["scenarios", dir] => scenarios(dir),
+        ["tune", target] => tune(target, 10),
+        ["tune", target, generations] => {
+            tune(target, generations.parse().expect("invalid generations"))
+        }
+        _ => usage(),
+    }
+}
 
-mod tree_search;
-use tree_search::*;
+fn usage() -> ! {
+    eprintln!(
+        "usage: snake [--color | --web <addr>] [--gif <out>] [--record <recording>] \
+         [--stats <log>] [--trace <out.folded>] [--rules tail-follow,growth=<n>,length=<n>] \
+         [--spawn shape=straight|coiled|random,margin=<n>] [--from <recording>:<moves>] \
+         [tree-search | watch <agent> | agents | play [width height] \
+         | assist [agent] | dashboard <agent> <agent> [agent] [agent] \
+         | train-q|train-sarsa <table> [episodes] | q <table> \
+         | evolve <genome> [generations] | neural <genome> \
+         | record <recording> [agent] \
+         | export <recording> <out.gif|out.svg> [moves] | scenarios [dir] \
+         | minimize <recording> <agent> <out.txt> [horizon] \
+         | sweep <spec> [out.csv] | compare <agent> <agent> [games [<width>x<height>]] \
+         | profile <agent> [games [<width>x<height>]] \
+         | tune average-path|tree-search [generations]]"
+    );
+    std::process::exit(2);
+}
 
-fn main() {
-    let mut game = SnakeGame::random(10, 10);
-    let mut agent = agents::average_path::AveragePath::default();
-    let mut renderer = render::Terminal {
-        sleep_time: std::time::Duration::from_millis(10),
-        render_every: 1,
+#[derive(Default)]
+enum Display {
+    #[default]
+    Terminal,
+    Color,
+    Web(String),
+}
+
+/// Where a watched game goes: one display, plus any number of files.
+#[derive(Default)]
+struct Output {
+    display: Display,
+    gif: Option<String>,
+    record: Option<String>,
+    stats: Option<String>,
+}
+
+impl Output {
+    fn renderers(&self) -> Vec<Box<dyn Renderer>> {
+        let mut renderers: Vec<Box<dyn Renderer>> = vec![match &self.display {
+            Display::Terminal => Box::new(render::Terminal::default()),
+            Display::Color => Box::new(render::Ansi::new(true)),
+            Display::Web(addr) => {
+                let web = render::web::Web::start(addr.as_str()).expect("failed to start server");
+                eprintln!("serving on http://{}", web.addr());
+                Box::new(web)
+            }
+        }];
+        if let Some(path) = &self.record {
+            renderers.push(Box::new(recording::Recorder::new(path)));
+        }
+        if let Some(path) = &self.stats {
+            renderers.push(Box::new(
+                render::stats::Stats::create(path).expect("failed to create stats log"),
+            ));
+        }
+        renderers
+    }
+
+    // The web page sets its own delay between frames, so the runner doesn't add its tick too.
+    fn runner(&self) -> Runner {
+        match self.display {
+            Display::Web(_) => Runner {
+                sleep_time: std::time::Duration::ZERO,
+                ..Default::default()
+            },
+            _ => Runner::default(),
+        }
+    }
+}
+
+/// How games start: fresh with the given rules and spawn, or partway through a recording.
+struct Setup {
+    rules: Rules,
+    spawn: Spawn,
+    from: Option<String>,
+}
+
+impl Setup {
+    // The starting game, and its seed if it starts from the beginning.
+    fn game(&self) -> (SnakeGame, Option<u64>) {
+        if let Some(from) = &self.from {
+            let (path, moves) = from
+                .rsplit_once(':')
+                .expect("expected --from <recording>:<moves>");
+            let recording = recording::Recording::load(path).expect("failed to load recording");
+            let game = recording
+                .game_at(moves.parse().expect("invalid moves"))
+                .unwrap_or_else(|| panic!("{} ends before move {}", path, moves));
+            return (game, None);
+        }
+
+        let seed = rand::random();
+        let game = SnakeGame::seeded_with_spawn(10, 10, seed, self.rules, self.spawn)
+            .unwrap_or_else(|e| panic!("{}", e));
+        (game, Some(seed))
+    }
+}
+
+fn watch(agent: &mut dyn Agent, name: &str, setup: &Setup, output: &Output) {
+    let (mut game, seed) = setup.game();
+    let mut gif = output.gif.as_ref().map(render::export::Gif::new);
+    let mut renderers = output.renderers();
+    if let Some(gif) = &mut gif {
+        renderers.push(Box::new(gif));
+    }
+    let terminal = output
+        .runner()
+        .run(&mut game, seed, agent, name, &mut renderers);
+    drop(renderers);
+    if let Some(mut gif) = gif {
+        gif.finish()
+            .unwrap_or_else(|e| panic!("failed to write {}: {}", gif.path.display(), e));
+    }
+    dbg!(terminal);
+}
+
+fn play(width: Coord, height: Coord) {
+    let interactive = interactive::Interactive {
+        width,
+        height,
+        ..Default::default()
+    };
+    interactive.play().expect("terminal error");
+}
+
+fn assist(agent: &mut dyn Agent) {
+    let interactive = interactive::Interactive::default();
+    interactive
+        .play_assisted(Some(agent))
+        .expect("terminal error");
+}
+
+fn dashboard(names: &[&str]) {
+    let mut agents = names
+        .iter()
+        .map(|name| search_agent(name))
+        .collect::<Vec<_>>();
+    let panes = names
+        .iter()
+        .copied()
+        .zip(
+            agents
+                .iter_mut()
+                .map(|agent| agent.as_mut() as &mut dyn Agent),
+        )
+        .collect();
+    dashboard::Dashboard::default()
+        .run(panes)
+        .expect("terminal error");
+}
+
+// An agent from a config string like `average-path:budget=500` or a `.toml` file.
+fn search_agent(arg: &str) -> Box<dyn Agent> {
+    AgentConfig::from_arg(arg)
+        .and_then(|config| config.build(&heuristics()))
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn list_agents() {
+    for entry in &agents::registry::AGENTS {
+        eprintln!("{:<14} {}", entry.name, entry.about);
+        for param in entry.params() {
+            eprintln!("  {:<20} {}", param.name, param.about);
+        }
+    }
+}
+
+fn record(path: &str, agent: &str, setup: &Setup) {
+    let mut renderers: Vec<Box<dyn Renderer>> = vec![
+        Box::new(recording::Recorder::new(path)),
+        Box::new(render::stats::Stats::stderr()),
+    ];
+    let runner = Runner {
+        sleep_time: std::time::Duration::ZERO,
+        ..Default::default()
+    };
+
+    let (mut game, seed) = setup.game();
+    assert!(
+        seed.is_some(),
+        "games resumed with --from can't be recorded"
+    );
+    runner.run(
+        &mut game,
+        seed,
+        search_agent(agent).as_mut(),
+        agent,
+        &mut renderers,
+    );
+}
+
+fn export(recording: &str, out: &str, moves: Option<usize>) {
+    let mut recording = recording::Recording::load(recording).expect("failed to load recording");
+
+    if out.ends_with(".svg") {
+        let moves = moves.unwrap_or(recording.actions.len());
+        let game = recording
+            .game_at(moves)
+            .unwrap_or_else(|| panic!("the recording ends before move {}", moves));
+        std::fs::write(out, render::export::svg(&game, 16)).expect("failed to write svg");
+        return;
+    }
+
+    if let Some(moves) = moves {
+        recording.actions.truncate(moves);
+    }
+    let runner = Runner {
+        sleep_time: std::time::Duration::ZERO,
         ..Default::default()
     };
+    let mut gif = render::export::Gif::new(out);
+    runner.replay(&recording, "replay", &mut gif);
+    gif.finish()
+        .unwrap_or_else(|e| panic!("failed to write {}: {}", out, e));
+}
+
+// Exits with 1 if `b` regressed against `a`, so it can gate changes.
+fn compare(a: &str, b: &str, games: Option<&str>, size: Option<&str>) {
+    let mut comparison = agents::compare::Comparison::default();
+    if let Some(games) = games {
+        comparison.games = games.parse().expect("invalid games");
+    }
+    if let Some(size) = size {
+        let (width, height) = size.split_once('x').expect("expected <width>x<height>");
+        comparison.width = width.parse().expect("invalid width");
+        comparison.height = height.parse().expect("invalid height");
+    }
+    let config = |arg| AgentConfig::from_arg(arg).unwrap_or_else(|e| panic!("{}", e));
+    let report = comparison
+        .run(&config(a), &config(b), &heuristics())
+        .unwrap_or_else(|e| panic!("{}", e));
+    print!("{}", report);
+    if report.regressed() {
+        std::process::exit(1);
+    }
+}
+
+// Plays seeds 0 to `games` and lists the slowest decisions, each of which can be replayed from
+// a recording of its seed with `--from`.
+fn profile(agent: &str, games: Option<&str>, size: Option<&str>, setup: &Setup) {
+    let games = games.map_or(10, |g| g.parse().expect("invalid games"));
+    let (width, height) = size.map_or((10, 10), |size| {
+        let (width, height) = size.split_once('x').expect("expected <width>x<height>");
+        (
+            width.parse().expect("invalid width"),
+            height.parse().expect("invalid height"),
+        )
+    });
+    let runner = Runner {
+        sleep_time: std::time::Duration::ZERO,
+        ..Default::default()
+    };
+
+    let mut profile = render::profile::Profile::new(10);
+    for seed in 0..games {
+        let mut game = SnakeGame::seeded_with_spawn(width, height, seed, setup.rules, setup.spawn)
+            .unwrap_or_else(|e| panic!("{}", e));
+        let terminal = runner.run(
+            &mut game,
+            Some(seed),
+            search_agent(agent).as_mut(),
+            agent,
+            &mut profile,
+        );
+        eprintln!("seed {}: {:?} with score {}", seed, terminal, game.score);
+    }
+    print!("{}", profile.slowest);
+}
+
+// Writes spans as folded stacks, which `inferno-flamegraph` and `flamegraph.pl` read.
+#[cfg(feature = "trace")]
+fn trace(path: &str) -> tracing_flame::FlushGuard<std::io::BufWriter<std::fs::File>> {
+    use tracing_subscriber::prelude::*;
+
+    let (layer, guard) =
+        tracing_flame::FlameLayer::with_file(path).expect("failed to create trace");
+    tracing_subscriber::registry().with(layer).init();
+    guard
+}
+
+fn sweep(spec: &str, csv: Option<&str>) {
+    let sweep = agents::sweep::Sweep::load(spec).expect("failed to load sweep");
+    eprintln!("{} trials of {} games", sweep.trials().len(), sweep.games);
+    let outcomes = sweep.run(&heuristics()).unwrap_or_else(|e| panic!("{}", e));
+    print!("{}", sweep.table(&outcomes));
+    if let Some(path) = csv {
+        std::fs::write(path, sweep.csv(&outcomes)).expect("failed to write csv");
+    }
+}
+
+// Writes the fixture and prints a test for it. Agents play with the default heuristics, as they
+// do in the tests.
+fn minimize(recording: &str, agent: &str, out: &str, horizon: Option<usize>) {
+    let mut minimizer = minimize::Minimizer::default();
+    if let Some(horizon) = horizon {
+        minimizer.horizon = horizon;
+    }
+    let name = std::path::Path::new(out)
+        .file_stem()
+        .expect("invalid fixture path")
+        .to_string_lossy();
+    let config = AgentConfig::from_arg(agent).unwrap_or_else(|e| panic!("{}", e));
+    let minimized = minimizer
+        .minimize(
+            &name,
+            &recording::Recording::load(recording).expect("failed to load recording"),
+            &config,
+            &agents::heuristic::HeuristicConfig::default(),
+        )
+        .unwrap_or_else(|e| panic!("{}", e));
+    eprintln!(
+        "died at move {}; from move {} it dies after {} more",
+        minimized.died_at, minimized.from, minimized.dies_after
+    );
+    std::fs::write(out, minimized.fixture(recording)).expect("failed to write fixture");
+    print!("{}", minimized.test());
+}
 
-    loop {
-        let action = agent.action(&game);
-        if let Some(terminal) = game.do_action(action) {
-            renderer.render(&game, true);
-            dbg!(terminal);
-            break;
+fn scenarios(dir: &str) {
+    let scenarios = scenario::Scenario::load_dir(dir).expect("failed to load scenarios");
+    let heuristics = heuristics();
+
+    let mut failures = 0;
+    for scenario in &scenarios {
+        eprintln!("{}", scenario.name);
+        let mut names = agents::PLAYABLE.to_vec();
+        for expectation in &scenario.expectations {
+            for name in &expectation.agents {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        for name in names {
+            let mut agent = agents::by_name(name, &heuristics)
+                .unwrap_or_else(|| panic!("unknown agent {:?}", name));
+            let run = scenario.run(agent.as_mut(), scenario.horizon().max(50));
+            let mut line = format!(
+                "  {:<14} {}",
+                name,
+                match (run.died_at, run.ate_at) {
+                    _ if run.won => format!("won after {}", run.moves),
+                    (Some(died), Some(ate)) => format!("ate at {}, died at {}", ate, died),
+                    (Some(died), None) => format!("died at {}", died),
+                    (None, Some(ate)) => format!("ate at {}, alive after {}", ate, run.moves),
+                    (None, None) => format!("alive after {}", run.moves),
+                }
+            );
+            let expected = scenario
+                .expectations
+                .iter()
+                .filter(|e| e.agents.iter().any(|a| a == name));
+            for expectation in expected {
+                match expectation.goal.check(&run) {
+                    Ok(()) => line.push_str(&format!("  ok {:?}", expectation.goal)),
+                    Err(e) => {
+                        failures += 1;
+                        line.push_str(&format!("  FAILED {:?}: {}", expectation.goal, e));
+                    }
+                }
+            }
+            eprintln!("{}", line);
         }
+    }
+
+    if failures > 0 {
+        eprintln!("{} expectations failed", failures);
+        std::process::exit(1);
+    }
+}
+
+fn train_q(command: &str, path: &str, episodes: usize) {
+    use agents::q_learning::*;
+
+    let training = Training {
+        method: if command == "train-sarsa" {
+            Method::Sarsa
+        } else {
+            Method::QLearning
+        },
+        episodes,
+        epsilon: Epsilon::Linear {
+            start: 1.0,
+            end: 0.01,
+            episodes: episodes * 4 / 5,
+        },
+        ..Default::default()
+    };
+
+    let mut agent = QLearning::default();
+    let scores = agent.train(&training);
+
+    let tail = &scores[scores.len().saturating_sub(100)..];
+    eprintln!(
+        "mean score over last {} episodes: {:.2}",
+        tail.len(),
+        tail.iter().sum::<usize>() as f32 / tail.len().max(1) as f32
+    );
+    agent.save(path).expect("failed to save table");
+}
+
+fn evolve(path: &str, generations: usize) {
+    let evolution = agents::neural::Evolution {
+        generations,
+        ..Default::default()
+    };
+
+    let genome = evolution
+        .run(|generation, fitness| {
+            eprintln!("generation {}: best fitness {:.2}", generation, fitness);
+        })
+        .unwrap_or_else(|e| panic!("{}", e));
+    genome.save(path).expect("failed to save genome");
+}
+
+fn heuristics() -> agents::heuristic::HeuristicConfig {
+    use agents::heuristic::HeuristicConfig;
+
+    HeuristicConfig::load_or_default(HeuristicConfig::DEFAULT_PATH)
+        .expect("failed to load heuristics")
+}
+
+fn tune(target: &str, generations: usize) {
+    use agents::{heuristic::HeuristicConfig, tuning::*};
+
+    let target = match target {
+        "average-path" => Target::AveragePath,
+        "tree-search" => Target::TreeSearch,
+        _ => panic!("unknown tuning target {:?}", target),
+    };
+    let tuning = Tuning {
+        target,
+        generations,
+        ..Default::default()
+    };
+
+    eprintln!(
+        "baseline fitness: {:.2}",
+        tuning.fitness(target.default_weights())
+    );
+    let weights = tuning.run(|generation, weights, fitness| {
+        eprintln!(
+            "generation {}: best fitness {:.2} with {:?}",
+            generation, fitness, weights
+        );
+    });
 
-        renderer.render(&game, false);
+    let mut config = heuristics();
+    match target {
+        Target::AveragePath => config.average_path = weights,
+        Target::TreeSearch => config.tree_search = weights,
     }
+    config
+        .save(HeuristicConfig::DEFAULT_PATH)
+        .expect("failed to save heuristics");
 }
@@ -0,0 +1,288 @@
+use crate::{
+    agents::{heuristic::HeuristicConfig, registry::AgentConfig},
+    gameplay::*,
+    recording::Recording,
+    scenario::{Expectation, Goal, Scenario},
+};
+
+/// Cuts a recorded game an agent lost down to the earliest position it still loses from.
+///
+/// Going forward from the start, a fresh agent plays each position for up to `horizon` moves.
+/// The first position it dies from is the scenario, as long as the snake could have survived from
+/// there; positions after the fatal decision can't be passed by any agent, so they're skipped.
+/// A shorter horizon gives a scenario that starts closer to the death. Every candidate before the
+/// death may be played, so long recordings of slow agents take a while.
+///
+/// Candidates are checked as the fixture will be read back, so later apples come from the
+/// recording's seed rather than the generator state mid-game, and nothing is left growing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minimizer {
+    /// Moves the agent gets from each candidate to die in.
+    pub horizon: usize,
+    /// Positions the survival search may visit per candidate. A position it runs out on counts
+    /// as survivable.
+    pub budget: usize,
+}
+
+/// A scenario the agent loses, and where in the recording it came from.
+#[derive(Debug, Clone)]
+pub struct Minimized {
+    pub scenario: Scenario,
+    pub agent: AgentConfig,
+    /// Moves into the recording the scenario starts at.
+    pub from: usize,
+    /// The move the agent died at in the recording.
+    pub died_at: usize,
+    /// The move the agent dies at when playing the scenario.
+    pub dies_after: usize,
+}
+
+impl Default for Minimizer {
+    fn default() -> Self {
+        Minimizer {
+            horizon: 200,
+            budget: 1_000_000,
+        }
+    }
+}
+
+impl Minimizer {
+    /// `name` is what the fixture will be called, without `.txt`.
+    pub fn minimize(
+        &self,
+        name: &str,
+        recording: &Recording,
+        agent: &AgentConfig,
+        heuristics: &HeuristicConfig,
+    ) -> Result<Minimized, String> {
+        agent.build(heuristics)?;
+        let died_at = recording
+            .replay()
+            .position(|(_, end)| end == Some(Terminal::Died))
+            .map(|i| i + 1)
+            .ok_or("the recorded game doesn't end in a death")?;
+
+        let games = std::iter::once(recording.start())
+            .chain(recording.replay().take(died_at - 1).map(|(game, _)| game))
+            .collect::<Vec<_>>();
+
+        for (from, game) in games.iter().enumerate() {
+            let mut scenario = Scenario::parse(name, &fixture(game, recording.seed))?;
+            let run = scenario.run(agent.build(heuristics)?.as_mut(), self.horizon);
+            let Some(dies_after) = run.died_at else {
+                continue;
+            };
+            // Checked after playing since the search is the slower of the two.
+            if !self.survivable(&scenario.game) {
+                continue;
+            }
+            if agent.params.is_empty() {
+                scenario.expectations.push(Expectation {
+                    agents: vec![agent.agent.clone()],
+                    goal: Goal::Survive(dies_after),
+                });
+            }
+            return Ok(Minimized {
+                scenario,
+                agent: agent.clone(),
+                from,
+                died_at,
+                dies_after,
+            });
+        }
+        Err(format!(
+            "{} doesn't die within {} moves from any survivable position in the recording",
+            agent, self.horizon
+        ))
+    }
+
+    // Whether some moves keep the snake alive until all of its current body has moved on.
+    fn survivable(&self, game: &SnakeGame) -> bool {
+        let mut visited = 0;
+        let depth = game.body.len() + game.growing;
+        self.survives(game, depth, &mut visited) != Some(false)
+    }
+
+    // `None` when the budget ran out before an answer.
+    fn survives(&self, game: &SnakeGame, depth: usize, visited: &mut usize) -> Option<bool> {
+        if depth == 0 {
+            return Some(true);
+        }
+        for action in Action::iter() {
+            *visited += 1;
+            if *visited > self.budget {
+                return None;
+            }
+            let mut next = game.clone();
+            match next.do_action(action) {
+                Some(Terminal::Won) => return Some(true),
+                Some(Terminal::Died) => {}
+                None => match self.survives(&next, depth - 1, visited) {
+                    Some(true) => return Some(true),
+                    Some(false) => {}
+                    None => return None,
+                },
+            }
+        }
+        Some(false)
+    }
+}
+
+impl Minimized {
+    /// The scenario file, with comments saying where it came from. Agents with parameters can't
+    /// be named in an `expect:` line, so for those the expectation is only a comment.
+    pub fn fixture(&self, source: &str) -> String {
+        let mut text = format!(
+            "# From move {} of {}, where {} died at move {}.\n",
+            self.from, source, self.agent, self.died_at
+        );
+        if !self.agent.params.is_empty() {
+            text.push_str(&format!(
+                "# {} should survive {}.\n",
+                self.agent, self.dies_after
+            ));
+        }
+        text.push_str(&self.scenario.to_string());
+        text.push('\n');
+        text
+    }
+
+    /// A test for `scenario.rs` that fails until the agent survives the scenario, expecting the
+    /// fixture in `scenarios/`.
+    pub fn test(&self) -> String {
+        let name = &self.scenario.name;
+        format!(
+            r#"#[test]
+fn {function}_survives() {{
+    use crate::agents::{{heuristic::HeuristicConfig, registry::AgentConfig}};
+
+    let text = include_str!("../scenarios/{name}.txt");
+    let scenario = Scenario::parse("{name}", text).unwrap();
+    let mut agent = AgentConfig::from_arg("{agent}")
+        .and_then(|config| config.build(&HeuristicConfig::default()))
+        .unwrap();
+    let run = scenario.run(agent.as_mut(), {moves});
+    Goal::Survive({moves}).check(&run).unwrap();
+}}
+"#,
+            function = name.replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+            name = name,
+            agent = self.agent,
+            moves = self.dies_after,
+        )
+    }
+}
+
+fn fixture(game: &SnakeGame, seed: u64) -> String {
+    Scenario {
+        name: String::new(),
+        game: game.clone(),
+        seed,
+        expectations: Vec::new(),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Greedy runs into itself quickly on a small board.
+    fn lost_game(seed: u64) -> Recording {
+        let mut recording = Recording::new(6, 6, seed, Rules::default(), Spawn::default());
+        let mut game = recording.start();
+        let mut agent = AgentConfig::named("greedy")
+            .build(&HeuristicConfig::default())
+            .unwrap();
+        loop {
+            let action = agent.action(&game);
+            recording.actions.push(action);
+            match game.do_action(action) {
+                Some(Terminal::Died) => return recording,
+                Some(Terminal::Won) => panic!("greedy won"),
+                None => {}
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_earliest_failing_position() {
+        let heuristics = HeuristicConfig::default();
+        let greedy = AgentConfig::named("greedy");
+        let minimizer = Minimizer {
+            horizon: 10,
+            ..Minimizer::default()
+        };
+        let recording = lost_game(6);
+        let minimized = minimizer
+            .minimize("lost", &recording, &greedy, &heuristics)
+            .unwrap();
+        assert_eq!(
+            (minimized.from, minimized.died_at, minimized.dies_after),
+            (25, 56, 6)
+        );
+        assert!(minimizer.survivable(&minimized.scenario.game));
+
+        // One move earlier, a fresh greedy lasts the whole horizon.
+        let mut game = recording.start();
+        for &action in &recording.actions[..minimized.from - 1] {
+            let _ = game.do_action(action);
+        }
+        let earlier = Scenario::parse("earlier", &fixture(&game, recording.seed)).unwrap();
+        let run = earlier.run(
+            greedy.build(&heuristics).unwrap().as_mut(),
+            minimizer.horizon,
+        );
+        assert_eq!(run.died_at, None);
+
+        // The fixture reads back as the same scenario, and greedy still loses it.
+        let fixture = minimized.fixture("lost.rec");
+        let scenario = Scenario::parse("lost", &fixture).unwrap();
+        assert_eq!(scenario.game, minimized.scenario.game);
+        let expectation = &scenario.expectations[0];
+        assert_eq!(expectation.goal, Goal::Survive(6));
+        let mut agent = greedy.build(&heuristics).unwrap();
+        let run = scenario.run(agent.as_mut(), 6);
+        assert!(expectation.goal.check(&run).is_err());
+
+        let test = minimized.test();
+        assert!(test.contains("fn lost_survives()"));
+        assert!(test.contains("include_str!(\"../scenarios/lost.txt\")"));
+    }
+
+    #[test]
+    fn needs_a_lost_game() {
+        let mut recording = Recording::new(8, 8, 1, Rules::default(), Spawn::default());
+        let mut game = recording.start();
+        let mut agent = AgentConfig::named("simple-path")
+            .build(&HeuristicConfig::default())
+            .unwrap();
+        for _ in 0..20 {
+            let action = agent.action(&game);
+            recording.actions.push(action);
+            let _ = game.do_action(action);
+        }
+        let err = Minimizer::default()
+            .minimize(
+                "x",
+                &recording,
+                &AgentConfig::named("greedy"),
+                &HeuristicConfig::default(),
+            )
+            .unwrap_err();
+        assert!(err.contains("doesn't end in a death"));
+    }
+
+    #[test]
+    fn survival_follows_the_rules() {
+        // The only move that doesn't hit a wall is into the tail.
+        let board = "3/0\n#####\n#++ #\n#<+O#\n#####";
+        let minimizer = Minimizer::default();
+        let strict = SnakeGame::from_board(board, 0, Rules::default()).unwrap();
+        assert!(!minimizer.survivable(&strict));
+        let rules = "tail-follow".parse().unwrap();
+        let tail_follow = SnakeGame::from_board(board, 0, rules).unwrap();
+        assert!(minimizer.survivable(&tail_follow));
+        assert!(minimizer.survivable(&SnakeGame::seeded(6, 6, 0)));
+    }
+}
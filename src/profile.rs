@@ -0,0 +1,164 @@
+//! Where agents spend their time deciding.
+//!
+//! The searches count their work on the thread they run on, and a runner takes the counts after
+//! each decision, so they stay per decision as long as the agent decides on the runner's thread.
+//! With the `trace` feature the same code also opens `tracing` spans: one per decision with the
+//! seed and move, and one per plan, cost and search inside it.
+
+use std::{cell::Cell, fmt, time::Duration};
+
+/// Work done by the searches behind a decision.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Work {
+    /// Partial plans taken off a search queue and expanded.
+    pub nodes: usize,
+    /// Games copied by `SnakeGame::do_many` to try out moves.
+    pub clones: usize,
+    /// Breadth-first searches over the board, for reachability and distances.
+    pub bfs: usize,
+}
+
+thread_local! {
+    static WORK: Cell<Work> = const {
+        Cell::new(Work {
+            nodes: 0,
+            clones: 0,
+            bfs: 0,
+        })
+    };
+}
+
+impl Work {
+    /// The work counted on this thread since the last call.
+    pub fn take() -> Work {
+        WORK.with(|work| work.take())
+    }
+
+    pub(crate) fn count(add: impl FnOnce(&mut Work)) {
+        WORK.with(|work| {
+            let mut counted = work.get();
+            add(&mut counted);
+            work.set(counted);
+        })
+    }
+}
+
+impl std::ops::AddAssign for Work {
+    fn add_assign(&mut self, other: Work) {
+        self.nodes += other.nodes;
+        self.clones += other.clones;
+        self.bfs += other.bfs;
+    }
+}
+
+/// One call to an agent's `action`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub seed: Option<u64>,
+    /// Moves played before the decision, as `--from <recording>:<moves>` takes them.
+    pub moves: usize,
+    pub took: Duration,
+    pub work: Work,
+}
+
+/// The slowest decisions out of all those added, and totals over all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slowest {
+    pub keep: usize,
+    pub decisions: usize,
+    pub took: Duration,
+    pub work: Work,
+    /// Slowest first.
+    pub slowest: Vec<Decision>,
+}
+
+impl Slowest {
+    pub fn new(keep: usize) -> Self {
+        Slowest {
+            keep,
+            decisions: 0,
+            took: Duration::ZERO,
+            work: Work::default(),
+            slowest: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, decision: Decision) {
+        self.decisions += 1;
+        self.took += decision.took;
+        self.work += decision.work;
+        let at = self.slowest.partition_point(|d| d.took >= decision.took);
+        if at < self.keep {
+            self.slowest.insert(at, decision);
+            self.slowest.truncate(self.keep);
+        }
+    }
+}
+
+impl fmt::Display for Slowest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mean = self
+            .took
+            .checked_div(self.decisions as u32)
+            .unwrap_or_default();
+        writeln!(
+            f,
+            "{} decisions in {:.2?}, mean {:.2?}; {} nodes, {} clones, {} bfs",
+            self.decisions, self.took, mean, self.work.nodes, self.work.clones, self.work.bfs
+        )?;
+        writeln!(
+            f,
+            "{:>20} {:>6} {:>10} {:>8} {:>8} {:>8}",
+            "seed", "move", "took", "nodes", "clones", "bfs"
+        )?;
+        for d in &self.slowest {
+            let seed = d.seed.map_or("-".to_string(), |s| s.to_string());
+            writeln!(
+                f,
+                "{:>20} {:>6} {:>10.2?} {:>8} {:>8} {:>8}",
+                seed, d.moves, d.took, d.work.nodes, d.work.clones, d.work.bfs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_slowest() {
+        let mut slowest = Slowest::new(3);
+        for (moves, ms) in [5, 1, 9, 3, 9, 7].into_iter().enumerate() {
+            slowest.add(Decision {
+                seed: Some(1),
+                moves,
+                took: Duration::from_millis(ms),
+                work: Work {
+                    nodes: 1,
+                    ..Default::default()
+                },
+            });
+        }
+        assert_eq!(slowest.decisions, 6);
+        assert_eq!(slowest.took, Duration::from_millis(34));
+        assert_eq!(slowest.work.nodes, 6);
+        // Ties keep the earlier decision first.
+        let moves = slowest.slowest.iter().map(|d| d.moves).collect::<Vec<_>>();
+        assert_eq!(moves, [2, 4, 5]);
+    }
+
+    #[cfg(feature = "agents")]
+    #[test]
+    fn counts_search_work() {
+        use crate::agents::{tree_search::TreeSearch, Agent};
+
+        Work::take();
+        let game = crate::SnakeGame::seeded(8, 8, 4);
+        TreeSearch::default().action(&game);
+        let work = Work::take();
+        assert!(work.nodes > 0 && work.bfs > 0, "{:?}", work);
+        assert_eq!(Work::take(), Work::default());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::gameplay::*;
+#[cfg(feature = "render")]
+use crate::render::{Event, Renderer};
+
+#[cfg(feature = "render")]
+use std::path::PathBuf;
+use std::{io, path::Path};
+
+/// A seeded game and the actions taken in it, enough to replay it exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recording {
+    pub width: Coord,
+    pub height: Coord,
+    pub seed: u64,
+    pub rules: Rules,
+    pub spawn: Spawn,
+    pub actions: Vec<Action>,
+}
+
+impl Recording {
+    pub fn new(width: Coord, height: Coord, seed: u64, rules: Rules, spawn: Spawn) -> Self {
+        Recording {
+            width,
+            height,
+            seed,
+            rules,
+            spawn,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Panics if the rules and spawn don't fit the board, which `load` checks and recordings of
+    /// played games can't run into.
+    pub fn start(&self) -> SnakeGame {
+        self.try_start().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_start(&self) -> Result<SnakeGame, String> {
+        SnakeGame::seeded_with_spawn(self.width, self.height, self.seed, self.rules, self.spawn)
+    }
+
+    /// Every state after the start, along with the outcome of the action that led to it. Stops
+    /// at the end of the game, even if there are actions after it.
+    pub fn replay(&self) -> impl Iterator<Item = (SnakeGame, Option<Terminal>)> + '_ {
+        let mut game = Some(self.start());
+        self.actions.iter().map_while(move |action| {
+            let mut next = game.take()?;
+            let terminal = next.do_action(*action);
+            if terminal.is_none() {
+                game = Some(next.clone());
+            }
+            Some((next, terminal))
+        })
+    }
+
+    /// The state after `moves` actions, or `None` if the recording or the game ends before then.
+    pub fn game_at(&self, moves: usize) -> Option<SnakeGame> {
+        match moves {
+            0 => Some(self.start()),
+            n => self.replay().nth(n - 1).map(|(game, _)| game),
+        }
+    }
+
+    /// Writes `<width>x<height> <seed> [rules [spawn]]` on the first line and one character per
+    /// action on the second: `S` straight, `L` left, `R` right. Rules and spawn are left out when
+    /// they're the default.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let actions = self
+            .actions
+            .iter()
+            .map(|a| match a {
+                Action::GoStraight => 'S',
+                Action::TurnLeft => 'L',
+                Action::TurnRight => 'R',
+            })
+            .collect::<String>();
+        let mut header = format!("{}x{} {}", self.width, self.height, self.seed);
+        let (rules, spawn) = (self.rules.to_string(), self.spawn.to_string());
+        if !spawn.is_empty() {
+            // Rules need a placeholder so the spawn stays in its column.
+            let rules = if rules.is_empty() { "strict" } else { &rules };
+            header = format!("{} {} {}", header, rules, spawn);
+        } else if !rules.is_empty() {
+            header = format!("{} {}", header, rules);
+        }
+        std::fs::write(path, format!("{}\n{}\n", header, actions))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let contents = std::fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        let header = lines.next().ok_or_else(|| invalid("missing header"))?;
+        let mut fields = header.split_whitespace();
+        let (size, seed) = fields
+            .next()
+            .zip(fields.next())
+            .ok_or_else(|| invalid("expected `<width>x<height> <seed>`"))?;
+        let rules = fields
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e: String| invalid(&e))?;
+        let spawn = fields
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e: String| invalid(&e))?;
+        let (width, height) = size
+            .split_once('x')
+            .ok_or_else(|| invalid("expected `<width>x<height>`"))?;
+
+        let actions = lines
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| match c {
+                'S' => Ok(Action::GoStraight),
+                'L' => Ok(Action::TurnLeft),
+                'R' => Ok(Action::TurnRight),
+                _ => Err(invalid("unknown action")),
+            })
+            .collect::<io::Result<_>>()?;
+
+        let recording = Recording {
+            width: width.parse().map_err(|_| invalid("invalid width"))?,
+            height: height.parse().map_err(|_| invalid("invalid height"))?,
+            seed: seed.parse().map_err(|_| invalid("invalid seed"))?,
+            rules,
+            spawn,
+            actions,
+        };
+        recording.try_start().map_err(|e| invalid(&e))?;
+        Ok(recording)
+    }
+}
+
+/// Records seeded games as they're played and saves them to `path` when they end.
+///
+/// Games started without a seed can't be replayed, so they're not recorded.
+#[cfg(feature = "render")]
+pub struct Recorder {
+    pub path: PathBuf,
+
+    recording: Option<Recording>,
+}
+
+#[cfg(feature = "render")]
+impl Recorder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Recorder {
+            path: path.into(),
+            recording: None,
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+impl Renderer for Recorder {
+    fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::Start { game, seed, .. } => {
+                self.recording = seed.map(|seed| {
+                    Recording::new(game.width, game.height, seed, game.rules, game.spawn)
+                });
+            }
+            Event::Step { action, .. } => {
+                if let Some(recording) = &mut self.recording {
+                    recording.actions.push(action);
+                }
+            }
+            Event::End { .. } => {
+                if let Some(recording) = self.recording.take() {
+                    recording
+                        .save(&self.path)
+                        .expect("failed to save recording");
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("snake-recording-{}", std::process::id()));
+        let setups = [
+            ("", ""),
+            ("tail-follow,growth=2,length=3", ""),
+            ("", "shape=coiled"),
+            ("length=4", "shape=random,margin=1"),
+        ];
+        for (rules, spawn) in setups {
+            let (rules, spawn) = (rules.parse().unwrap(), spawn.parse().unwrap());
+            let mut recording = Recording::new(7, 5, 42, rules, spawn);
+            recording.actions = vec![Action::GoStraight, Action::TurnLeft, Action::TurnRight];
+            recording.save(&path).unwrap();
+            assert_eq!(Recording::load(&path).unwrap(), recording);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn replay_stops_at_the_end_of_the_game() {
+        // Going straight from the start runs into the wall after a few moves.
+        let mut recording = Recording::new(5, 5, 0, Rules::default(), Spawn::default());
+        recording.actions = vec![Action::GoStraight; 10];
+        let replayed = recording.replay().collect::<Vec<_>>();
+        let (last, terminal) = replayed.last().unwrap();
+        assert_eq!(*terminal, Some(Terminal::Died));
+        assert!(replayed[..replayed.len() - 1]
+            .iter()
+            .all(|(_, t)| t.is_none()));
+
+        assert_eq!(recording.game_at(0), Some(recording.start()));
+        assert_eq!(recording.game_at(replayed.len()).as_ref(), Some(last));
+        assert_eq!(recording.game_at(replayed.len() + 1), None);
+        recording.actions.truncate(2);
+        assert_eq!(recording.game_at(3), None);
+    }
+
+    #[test]
+    fn load_rejects_setups_that_cant_start() {
+        let path = std::env::temp_dir().join(format!("snake-unstartable-{}", std::process::id()));
+        std::fs::write(&path, "10x10 0 length=9 margin=2\nS\n").unwrap();
+        let err = Recording::load(&path).unwrap_err();
+        assert!(err.to_string().contains("no room"), "{}", err);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,78 +1,243 @@
-use crate::{Cell, Heading, Occupant, SnakeGame};
-use std::time::Duration;
+use crate::{Action, Cell, SnakeGame};
+use crossterm::{cursor, execute, queue, terminal};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+pub mod ansi;
+pub use ansi::Ansi;
+
+pub mod export;
+pub mod profile;
+pub mod stats;
+pub mod web;
+
+/// Something that happened in a game, in the order a runner reports them.
+///
+/// Each move produces `Diagnostics` from the agent, then `Step`, then `AppleEaten` if it scored,
+/// then either `Frame` if the runner wants the move drawn or `End` if the game is over.
+pub enum Event<'a> {
+    Start {
+        game: &'a SnakeGame,
+        agent: &'a str,
+        seed: Option<u64>,
+    },
+    Diagnostics {
+        took: Duration,
+        /// What the searches behind the decision did, see `profile`.
+        work: crate::profile::Work,
+        plan: Option<&'a [Action]>,
+    },
+    Step {
+        game: &'a SnakeGame,
+        action: Action,
+    },
+    AppleEaten {
+        game: &'a SnakeGame,
+    },
+    Frame {
+        game: &'a SnakeGame,
+    },
+    End {
+        game: &'a SnakeGame,
+        /// `None` if the game was stopped before it finished, such as a truncated replay.
+        outcome: Option<crate::gameplay::Terminal>,
+    },
+}
 
 pub trait Renderer {
-    fn render(&mut self, game: &SnakeGame, final_: bool);
+    fn handle(&mut self, event: &Event);
 }
 
+impl<R: Renderer + ?Sized> Renderer for &mut R {
+    fn handle(&mut self, event: &Event) {
+        (**self).handle(event);
+    }
+}
+
+impl Renderer for Vec<Box<dyn Renderer + '_>> {
+    fn handle(&mut self, event: &Event) {
+        for renderer in self.iter_mut() {
+            renderer.handle(event);
+        }
+    }
+}
+
+#[derive(Default)]
 pub struct Terminal {
-    pub render_every: usize,
-    pub sleep_time: Duration,
+    cleared: bool,
 }
 
 impl Renderer for Terminal {
-    fn render(&mut self, game: &SnakeGame, final_: bool) {
-        let should_render =
-            final_ || (self.render_every == 0 || game.moves % self.render_every == 0);
-        if !should_render {
-            return;
+    fn handle(&mut self, event: &Event) {
+        if let Event::Frame { game } | Event::End { game, .. } = event {
+            self.draw(&dbg_string(game)).expect("failed to draw");
         }
-
-        clearscreen::clear().expect("failed to clear screen");
-        dbg_print(game);
-        std::thread::sleep(self.sleep_time);
     }
 }
 
-impl Default for Terminal {
-    fn default() -> Self {
-        Terminal {
-            render_every: 1,
-            sleep_time: Duration::from_millis(10),
+impl Terminal {
+    // Redraws in place by moving the cursor home and overwriting each line, which avoids the
+    // flicker of clearing the whole screen every frame. Uses `\r\n` so it also works in raw mode.
+    pub fn draw(&mut self, text: &str) -> io::Result<()> {
+        let mut out = io::stderr().lock();
+        if !self.cleared {
+            queue!(out, terminal::Clear(terminal::ClearType::All))?;
+            self.cleared = true;
+        }
+        queue!(out, cursor::MoveTo(0, 0))?;
+        for line in text.lines() {
+            write!(out, "{}", line)?;
+            queue!(out, terminal::Clear(terminal::ClearType::UntilNewLine))?;
+            write!(out, "\r\n")?;
         }
+        queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        out.flush()
+    }
+}
+
+/// Raw mode on the alternate screen with the cursor hidden, for as long as it's alive. Dropping
+/// it puts the terminal back, so a panic or an early `?` doesn't leave the shell unusable.
+pub struct FullScreen(());
+
+impl FullScreen {
+    pub fn enter() -> io::Result<FullScreen> {
+        terminal::enable_raw_mode()?;
+        // From here on, dropping the guard undoes whatever was turned on.
+        let screen = FullScreen(());
+        execute!(io::stderr(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for FullScreen {
+    fn drop(&mut self) {
+        // Nothing useful can be done about errors here, and the terminal may already be gone.
+        let _ = execute!(io::stderr(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
     }
 }
 
 pub fn dbg_print(game: &SnakeGame) {
-    use std::fmt::Write;
+    eprintln!("{}", dbg_string(game));
+}
 
-    let mut s = String::with_capacity((game.width as usize + 2) * (game.height as usize + 2));
-    write!(&mut s, "{}/{}\n", game.score, game.moves).unwrap();
+pub fn dbg_string(game: &SnakeGame) -> String {
+    dbg_string_with_path(game, &[])
+}
 
-    for _ in 0..(game.width + 2) {
-        s.push('#');
+// Like `dbg_string`, with the empty cells of `path` drawn as `.`.
+pub fn dbg_string_with_path(game: &SnakeGame, path: &[Cell]) -> String {
+    game.draw(path)
+}
+
+// Cells the head passes through when taking `actions`, ignoring collisions.
+pub fn path_cells(game: &SnakeGame, actions: &[Action]) -> Vec<Cell> {
+    let mut cell = game.head();
+    let mut heading = game.heading;
+    let mut cells = Vec::new();
+    for action in actions {
+        heading = heading.after(*action);
+        cell = match game.cell_delta(cell, heading) {
+            Some(c) => c,
+            None => break,
+        };
+        cells.push(cell);
     }
-    s.push('\n');
-
-    for row in 0..game.height {
-        s.push('#');
-        for col in 0..game.width {
-            let cell = Cell(col, row);
-            let c = match game.cell_occupant(cell) {
-                None => ' ',
-                Some(Occupant::Body) => {
-                    if game.head() == cell {
-                        match game.heading {
-                            Heading::North => '^',
-                            Heading::South => 'v',
-                            Heading::East => '>',
-                            Heading::West => '<',
-                        }
-                    } else {
-                        '+'
-                    }
-                }
-                Some(Occupant::Apple) => 'O',
-            };
-            s.push(c);
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Coord, Heading};
+
+    fn game(
+        width: Coord,
+        height: Coord,
+        body: &[Cell],
+        heading: Heading,
+        apple: Cell,
+    ) -> SnakeGame {
+        let mut game = SnakeGame::seeded(width, height, 0);
+        game.body = body.iter().copied().collect();
+        game.heading = heading;
+        game.apple = apple;
+        game.score = body.len() - 1;
+        game.moves = 7;
+        game
+    }
+
+    #[test]
+    fn single_cell_snake() {
+        let game = game(4, 3, &[Cell(1, 1)], Heading::North, Cell(3, 2));
+        assert_eq!(
+            dbg_string(&game),
+            "0/7\n\
+             ######\n\
+             #    #\n\
+             # ^  #\n\
+             #   O#\n\
+             ######"
+        );
+    }
+
+    #[test]
+    fn head_glyph_follows_heading() {
+        for (heading, glyph) in [
+            (Heading::North, '^'),
+            (Heading::South, 'v'),
+            (Heading::East, '>'),
+            (Heading::West, '<'),
+        ] {
+            let game = game(1, 2, &[Cell(0, 0)], heading, Cell(0, 1));
+            assert_eq!(
+                dbg_string(&game),
+                format!("0/7\n###\n#{}#\n#O#\n###", glyph)
+            );
         }
-        s.push('#');
-        s.push('\n');
     }
 
-    for _ in 0..(game.width + 2) {
-        s.push('#');
+    #[test]
+    fn bent_body() {
+        let body = [Cell(0, 0), Cell(1, 0), Cell(1, 1), Cell(2, 1)];
+        let game = game(4, 3, &body, Heading::East, Cell(0, 2));
+        assert_eq!(
+            dbg_string(&game),
+            "3/7\n\
+             ######\n\
+             #++  #\n\
+             # +> #\n\
+             #O   #\n\
+             ######"
+        );
+    }
+
+    #[test]
+    fn path_drawn_in_empty_cells() {
+        let body = [Cell(0, 0), Cell(1, 0)];
+        let game = game(4, 3, &body, Heading::East, Cell(3, 1));
+        let path = path_cells(
+            &game,
+            &[Action::GoStraight, Action::TurnRight, Action::TurnLeft],
+        );
+        assert_eq!(path, [Cell(2, 0), Cell(2, 1), Cell(3, 1)]);
+        assert_eq!(
+            dbg_string_with_path(&game, &path),
+            "1/7\n\
+             ######\n\
+             #+>. #\n\
+             #  .O#\n\
+             #    #\n\
+             ######"
+        );
     }
 
-    eprintln!("{}", s);
+    #[test]
+    fn path_stops_at_walls() {
+        let game = game(3, 1, &[Cell(1, 0)], Heading::East, Cell(0, 0));
+        let path = path_cells(&game, &[Action::GoStraight; 3]);
+        assert_eq!(path, [Cell(2, 0)]);
+    }
 }
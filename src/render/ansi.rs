@@ -0,0 +1,328 @@
+use super::{path_cells, Event, Renderer};
+use crate::{Action, Cell, Heading, SnakeGame};
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Write as _},
+    time::Duration,
+};
+
+const HEAD_COLOR: (u8, u8, u8) = (120, 255, 120);
+const TAIL_COLOR: (u8, u8, u8) = (20, 100, 40);
+const APPLE_COLOR: (u8, u8, u8) = (255, 70, 70);
+const WALL_COLOR: (u8, u8, u8) = (110, 110, 110);
+const PATH_COLOR: (u8, u8, u8) = (230, 200, 60);
+
+/// Colored renderer drawing the body with box-drawing segments that follow its connectivity.
+///
+/// Cells are two columns wide so horizontal connections can be drawn between them. Each frame is
+/// built into a buffer and written to stderr at once.
+#[derive(Default)]
+pub struct Ansi {
+    pub show_plan: bool,
+
+    agent: String,
+    decision_time: Duration,
+    plan: Vec<Action>,
+    buffer: String,
+    cleared: bool,
+}
+
+impl Ansi {
+    pub fn new(show_plan: bool) -> Self {
+        Ansi {
+            show_plan,
+            ..Default::default()
+        }
+    }
+
+    pub fn frame(&mut self, game: &SnakeGame) -> &str {
+        self.buffer.clear();
+        let s = &mut self.buffer;
+        if !self.cleared {
+            s.push_str("\x1b[2J");
+            self.cleared = true;
+        }
+        s.push_str("\x1b[H");
+
+        writeln!(
+            s,
+            "{}  score {}  moves {}  decision {:.2?}\x1b[K",
+            self.agent, game.score, game.moves, self.decision_time
+        )
+        .unwrap();
+
+        let path = if self.show_plan {
+            path_cells(game, &self.plan)
+        } else {
+            Vec::new()
+        };
+        let segments = segments(game);
+
+        let wall = |s: &mut String, width: usize| {
+            fg(s, WALL_COLOR);
+            s.push('█');
+            for _ in 0..width {
+                s.push_str("██");
+            }
+            s.push('█');
+            s.push_str("\x1b[0m\x1b[K\n");
+        };
+
+        wall(s, game.width as usize);
+        for row in 0..game.height {
+            fg(s, WALL_COLOR);
+            s.push('█');
+            for col in 0..game.width {
+                let cell = Cell(col, row);
+                match segments.get(&cell) {
+                    Some(segment) => {
+                        fg(s, segment.color);
+                        s.push(segment.glyph);
+                        s.push(if segment.east { '─' } else { ' ' });
+                    }
+                    None if cell == game.apple => {
+                        fg(s, APPLE_COLOR);
+                        s.push_str("● ");
+                    }
+                    None if path.contains(&cell) => {
+                        fg(s, PATH_COLOR);
+                        s.push_str("· ");
+                    }
+                    None => s.push_str("  "),
+                }
+            }
+            fg(s, WALL_COLOR);
+            s.push('█');
+            s.push_str("\x1b[0m\x1b[K\n");
+        }
+        wall(s, game.width as usize);
+        s.push_str("\x1b[J");
+
+        &self.buffer
+    }
+}
+
+impl Renderer for Ansi {
+    fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::Start { agent, .. } => self.agent = agent.to_string(),
+            Event::Diagnostics { took, plan, .. } => {
+                self.decision_time = took;
+                self.plan = plan.map(|p| p.to_vec()).unwrap_or_default();
+            }
+            Event::Frame { game } | Event::End { game, .. } => {
+                let frame = self.frame(game).replace('\n', "\r\n");
+                let mut out = io::stderr().lock();
+                out.write_all(frame.as_bytes())
+                    .and_then(|_| out.flush())
+                    .expect("failed to draw");
+            }
+            Event::Step { .. } | Event::AppleEaten { .. } => {}
+        }
+    }
+}
+
+fn fg(s: &mut String, (r, g, b): (u8, u8, u8)) {
+    write!(s, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+}
+
+struct Segment {
+    glyph: char,
+    color: (u8, u8, u8),
+    // Whether the segment connects to the cell to its right.
+    east: bool,
+}
+
+fn segments(game: &SnakeGame) -> HashMap<Cell, Segment> {
+    let len = game.body.len();
+    let direction = |from: Cell, to: Cell| from.heading_toward(to);
+
+    game.body
+        .iter()
+        .enumerate()
+        .map(|(i, &cell)| {
+            let toward_tail = i.checked_sub(1).map(|j| game.body[j]);
+            let toward_head = game.body.get(i + 1).copied();
+            let links = [toward_tail, toward_head]
+                .into_iter()
+                .flatten()
+                .filter_map(|other| direction(cell, other))
+                .collect::<Vec<_>>();
+
+            let glyph = if i + 1 == len {
+                match game.heading {
+                    Heading::North => '▲',
+                    Heading::South => '▼',
+                    Heading::East => '▶',
+                    Heading::West => '◀',
+                }
+            } else {
+                glyph(&links)
+            };
+
+            // Fade from the head color at the head to the tail color at the tail.
+            let t = if len > 1 {
+                i as f32 / (len - 1) as f32
+            } else {
+                1.0
+            };
+            let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            let color = (
+                lerp(TAIL_COLOR.0, HEAD_COLOR.0),
+                lerp(TAIL_COLOR.1, HEAD_COLOR.1),
+                lerp(TAIL_COLOR.2, HEAD_COLOR.2),
+            );
+
+            let segment = Segment {
+                glyph,
+                color,
+                east: links.contains(&Heading::East),
+            };
+            (cell, segment)
+        })
+        .collect()
+}
+
+fn glyph(links: &[Heading]) -> char {
+    use Heading::*;
+
+    let has = |h| links.contains(&h);
+    match (has(North), has(South), has(East), has(West)) {
+        (true, true, _, _) => '│',
+        (_, _, true, true) => '─',
+        (false, true, true, _) => '┌',
+        (false, true, _, true) => '┐',
+        (true, false, true, _) => '└',
+        (true, false, _, true) => '┘',
+        (true, false, false, false) => '╵',
+        (false, true, false, false) => '╷',
+        (false, false, true, false) => '╶',
+        (false, false, false, true) => '╴',
+        _ => '■',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The frame with its escape sequences taken out, leaving the glyphs.
+    fn plain(frame: &str) -> String {
+        let mut s = String::new();
+        let mut chars = frame.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| c.is_ascii_alphabetic());
+            } else {
+                s.push(c);
+            }
+        }
+        s
+    }
+
+    // A body that winds through every kind of bend, from the tail at the bottom left to the head
+    // at the bottom right.
+    fn winding() -> SnakeGame {
+        let mut game = SnakeGame::seeded(4, 3, 0);
+        game.body = [
+            (0, 2),
+            (0, 1),
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (2, 1),
+            (1, 1),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+        ]
+        .into_iter()
+        .map(|(x, y)| Cell(x, y))
+        .collect();
+        game.heading = Heading::East;
+        game.apple = Cell(3, 0);
+        game.score = 9;
+        game.moves = 7;
+        game
+    }
+
+    #[test]
+    fn body_connections() {
+        let mut ansi = Ansi::new(false);
+        let frame = plain(ansi.frame(&winding()));
+        let lines = frame.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "  score 9  moves 7  decision 0.00ns");
+        assert_eq!(
+            lines[1..],
+            [
+                "██████████",
+                "█┌───┐ ● █",
+                "█│ ┌─┘   █",
+                "█╵ └───▶ █",
+                "██████████",
+            ]
+        );
+    }
+
+    #[test]
+    fn glyph_for_each_connection() {
+        use Heading::*;
+
+        for (links, expected) in [
+            (&[North, South][..], '│'),
+            (&[East, West], '─'),
+            (&[South, East], '┌'),
+            (&[South, West], '┐'),
+            (&[North, East], '└'),
+            (&[North, West], '┘'),
+            (&[North], '╵'),
+            (&[South], '╷'),
+            (&[East], '╶'),
+            (&[West], '╴'),
+            (&[], '■'),
+        ] {
+            assert_eq!(glyph(links), expected, "{:?}", links);
+            let reversed = links.iter().rev().copied().collect::<Vec<_>>();
+            assert_eq!(glyph(&reversed), expected, "{:?}", reversed);
+        }
+    }
+
+    #[test]
+    fn colors_fade_from_tail_to_head() {
+        let game = winding();
+        let body = segments(&game);
+        let color = |x, y| body[&Cell(x, y)].color;
+        assert_eq!(color(0, 2), TAIL_COLOR);
+        assert_eq!(color(3, 2), HEAD_COLOR);
+        // Five ninths of the way along.
+        assert_eq!(color(2, 1), (76, 186, 84));
+
+        let frame = Ansi::new(false).frame(&game).to_string();
+        assert!(frame.contains("\x1b[38;2;120;255;120m▶"));
+        assert!(frame.contains("\x1b[38;2;20;100;40m╵"));
+
+        let mut single = game.clone();
+        single.body = [Cell(1, 1)].into_iter().collect();
+        assert_eq!(segments(&single)[&Cell(1, 1)].color, HEAD_COLOR);
+    }
+
+    #[test]
+    fn plan_drawn_in_empty_cells() {
+        let mut game = SnakeGame::seeded(4, 3, 0);
+        game.body = [Cell(0, 0), Cell(1, 0)].into_iter().collect();
+        game.heading = Heading::East;
+        game.apple = Cell(3, 1);
+
+        let mut ansi = Ansi::new(true);
+        ansi.handle(&Event::Diagnostics {
+            took: Duration::ZERO,
+            work: Default::default(),
+            plan: Some(&[Action::GoStraight, Action::TurnRight, Action::TurnLeft]),
+        });
+        let frame = plain(ansi.frame(&game));
+        assert_eq!(frame.lines().nth(2), Some("█╶─▶ ·   █"));
+        assert_eq!(frame.lines().nth(3), Some("█    · ● █"));
+    }
+}
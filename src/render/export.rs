@@ -0,0 +1,320 @@
+use super::{Event, Renderer};
+use crate::SnakeGame;
+
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+    time::Duration,
+};
+
+const BACKGROUND: u8 = 0;
+const WALL: u8 = 1;
+const APPLE: u8 = 2;
+// Body colors run from the tail at `BODY` to the head at `BODY + SHADES - 1`.
+const BODY: u8 = 3;
+const SHADES: u8 = 16;
+
+fn palette() -> Vec<[u8; 3]> {
+    let (tail, head) = ([20.0, 100.0, 40.0], [120.0, 255.0, 120.0]);
+    let mut colors = vec![[24, 24, 24], [110, 110, 110], [230, 60, 60]];
+    colors.extend((0..SHADES).map(|i| {
+        let t = i as f32 / (SHADES - 1) as f32;
+        [0, 1, 2].map(|c: usize| (tail[c] + (head[c] - tail[c]) * t).round() as u8)
+    }));
+    colors
+}
+
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color: u8,
+}
+
+// The board drawn as filled rectangles, in painting order, with walls one cell thick around it.
+fn shapes(game: &SnakeGame, cell: u32) -> Vec<Rect> {
+    let inset = (cell / 8).max(1);
+    let (board_w, board_h) = (game.width as u32 + 2, game.height as u32 + 2);
+
+    let mut rects = vec![
+        Rect {
+            x: 0,
+            y: 0,
+            width: board_w * cell,
+            height: board_h * cell,
+            color: WALL,
+        },
+        Rect {
+            x: cell,
+            y: cell,
+            width: game.width as u32 * cell,
+            height: game.height as u32 * cell,
+            color: BACKGROUND,
+        },
+    ];
+
+    let inner = |c: crate::Cell, color| Rect {
+        x: (c.0 as u32 + 1) * cell + inset,
+        y: (c.1 as u32 + 1) * cell + inset,
+        width: cell - 2 * inset,
+        height: cell - 2 * inset,
+        color,
+    };
+
+    rects.push(inner(game.apple, APPLE));
+
+    let len = game.body.len();
+    for (i, &segment) in game.body.iter().enumerate() {
+        let shade = if len > 1 {
+            (i * (SHADES as usize - 1) / (len - 1)) as u8
+        } else {
+            SHADES - 1
+        };
+        let rect = inner(segment, BODY + shade);
+
+        // Bridging consecutive segments shows how the body connects.
+        if let Some(&next) = game.body.get(i + 1) {
+            let other = inner(next, rect.color);
+            let (x, y) = (rect.x.min(other.x), rect.y.min(other.y));
+            rects.push(Rect {
+                x,
+                y,
+                width: (rect.x + rect.width).max(other.x + other.width) - x,
+                height: (rect.y + rect.height).max(other.y + other.height) - y,
+                color: rect.color,
+            });
+        } else {
+            rects.push(rect);
+        }
+    }
+    rects
+}
+
+/// Renders a single frame as an SVG document, with each cell `cell` pixels wide.
+pub fn svg(game: &SnakeGame, cell: u32) -> String {
+    let palette = palette();
+    let (width, height) = (
+        (game.width as u32 + 2) * cell,
+        (game.height as u32 + 2) * cell,
+    );
+
+    let mut s = String::new();
+    writeln!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )
+    .unwrap();
+    for rect in shapes(game, cell) {
+        let [r, g, b] = palette[rect.color as usize];
+        writeln!(
+            s,
+            r##"  <rect x="{}" y="{}" width="{}" height="{}" fill="#{:02x}{:02x}{:02x}"/>"##,
+            rect.x, rect.y, rect.width, rect.height, r, g, b
+        )
+        .unwrap();
+    }
+    writeln!(
+        s,
+        r##"  <text x="{}" y="{}" font-family="monospace" font-size="{}" fill="#ffffff">{}/{}</text>"##,
+        cell / 4,
+        cell * 3 / 4,
+        cell * 3 / 4,
+        game.score,
+        game.moves
+    )
+    .unwrap();
+    s.push_str("</svg>\n");
+    s
+}
+
+fn rasterize(game: &SnakeGame, cell: u32) -> Vec<u8> {
+    let width = (game.width as u32 + 2) * cell;
+    let height = (game.height as u32 + 2) * cell;
+    let mut pixels = vec![BACKGROUND; (width * height) as usize];
+    for rect in shapes(game, cell) {
+        for y in rect.y..rect.y + rect.height {
+            let row = (y * width) as usize;
+            pixels[row + rect.x as usize..row + (rect.x + rect.width) as usize].fill(rect.color);
+        }
+    }
+    pixels
+}
+
+/// Writes every rendered frame to an animated GIF, finishing the file on the final frame.
+///
+/// Renderers can't fail, so the first error writing the file is kept for `finish`, and frames
+/// after it are dropped.
+pub struct Gif {
+    pub path: PathBuf,
+    pub cell_size: u32,
+    pub delay: Duration,
+
+    encoder: Option<gif::Encoder<BufWriter<File>>>,
+    error: Option<io::Error>,
+}
+
+impl Gif {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Gif {
+            path: path.into(),
+            cell_size: 16,
+            delay: Duration::from_millis(50),
+            encoder: None,
+            error: None,
+        }
+    }
+
+    /// Finishes the file if the game didn't end, and returns the first error writing it.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.encoder = None;
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    fn write_frame(&mut self, game: &SnakeGame) -> io::Result<()> {
+        // GIF dimensions are 16 bits, so big boards at big cell sizes can't be written.
+        let side = |cells: crate::Coord| {
+            (cells as u32 + 2)
+                .checked_mul(self.cell_size)
+                .and_then(|pixels| u16::try_from(pixels).ok())
+        };
+        let (width, height) = match (side(game.width), side(game.height)) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "a {}x{} board at {} pixels per cell is too large for a GIF",
+                        game.width, game.height, self.cell_size
+                    ),
+                ))
+            }
+        };
+
+        if self.encoder.is_none() {
+            let file = BufWriter::new(File::create(&self.path)?);
+            let palette = palette().concat();
+            let mut encoder =
+                gif::Encoder::new(file, width, height, &palette).map_err(io::Error::other)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(io::Error::other)?;
+            self.encoder = Some(encoder);
+        }
+
+        let pixels = rasterize(game, self.cell_size);
+        let frame = gif::Frame {
+            width,
+            height,
+            delay: (self.delay.as_millis() / 10) as u16,
+            buffer: Cow::Borrowed(&pixels),
+            ..Default::default()
+        };
+        self.encoder
+            .as_mut()
+            .unwrap()
+            .write_frame(&frame)
+            .map_err(io::Error::other)
+    }
+}
+
+impl Renderer for Gif {
+    fn handle(&mut self, event: &Event) {
+        let result = match event {
+            Event::Frame { game } if self.error.is_none() => self.write_frame(game),
+            Event::End { game, .. } if self.error.is_none() => {
+                let result = self.write_frame(game);
+                // Dropping the encoder writes the GIF trailer.
+                self.encoder = None;
+                result
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two body segments and an apple on a 3x1 board, drawn at 4 pixels per cell.
+    fn game() -> SnakeGame {
+        "0/0\n#####\n#+>O#\n#####".parse().unwrap()
+    }
+
+    #[test]
+    fn rasterizes_walls_apple_and_body() {
+        let pixels = rasterize(&game(), 4);
+        let (width, height) = (5 * 4, 3 * 4);
+        assert_eq!(pixels.len(), width * height);
+        let at = |x: usize, y: usize| pixels[y * width + x];
+
+        assert_eq!((at(0, 0), at(3, 3), at(19, 11)), (WALL, WALL, WALL));
+        assert_eq!((at(4, 4), at(12, 5)), (BACKGROUND, BACKGROUND));
+        assert_eq!(at(13, 5), APPLE);
+        // The tail and the bridge to the head share the tail's shade; the head is the last.
+        assert_eq!((at(5, 5), at(8, 5)), (BODY, BODY));
+        assert_eq!(at(9, 5), BODY + SHADES - 1);
+    }
+
+    #[test]
+    fn svg_draws_every_shape_and_the_score() {
+        let svg = svg(&game(), 4);
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="12" viewBox="0 0 20 12">"#
+        ));
+        assert!(svg.ends_with("</svg>\n"));
+        // Walls, background, apple, the tail's bridge and the head.
+        assert_eq!(svg.matches("<rect ").count(), 5);
+        assert!(svg.contains(r##"<rect x="13" y="5" width="2" height="2" fill="#e63c3c"/>"##));
+        assert!(svg.contains(r##"<rect x="5" y="5" width="6" height="2" fill="#146428"/>"##));
+        assert!(svg.contains(">0/0</text>"));
+    }
+
+    #[test]
+    fn rejects_frames_too_large_for_a_gif() {
+        let path = std::env::temp_dir().join(format!("snake-too-large-{}.gif", std::process::id()));
+        let mut gif = Gif::new(&path);
+        gif.cell_size = 20_000;
+        let err = gif.write_frame(&game()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keeps_the_first_error_for_finish() {
+        let game = game();
+        let dir = std::env::temp_dir().join(format!("snake-gif-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut gif = Gif::new(dir.join("game.gif"));
+        gif.handle(&Event::Frame { game: &game });
+        gif.handle(&Event::End {
+            game: &game,
+            outcome: None,
+        });
+        gif.finish().unwrap();
+        let bytes = std::fs::read(dir.join("game.gif")).unwrap();
+        assert!(bytes.starts_with(b"GIF89a") && bytes.ends_with(&[0x3b]));
+
+        // The directory can't be created as a file, and the error waits for `finish`.
+        let mut gif = Gif::new(&dir);
+        gif.handle(&Event::Frame { game: &game });
+        gif.cell_size = 20_000;
+        gif.handle(&Event::Frame { game: &game });
+        assert_ne!(
+            gif.finish().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(gif.finish().is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,38 @@
+use super::{Event, Renderer};
+use crate::profile::{Decision, Slowest};
+
+/// Collects the slowest decisions over every game it sees, with the seed and move of each.
+pub struct Profile {
+    pub slowest: Slowest,
+    seed: Option<u64>,
+    moves: usize,
+}
+
+impl Profile {
+    pub fn new(keep: usize) -> Self {
+        Profile {
+            slowest: Slowest::new(keep),
+            seed: None,
+            moves: 0,
+        }
+    }
+}
+
+impl Renderer for Profile {
+    fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::Start { game, seed, .. } => {
+                self.seed = seed;
+                self.moves = game.moves;
+            }
+            Event::Diagnostics { took, work, .. } => self.slowest.add(Decision {
+                seed: self.seed,
+                moves: self.moves,
+                took,
+                work,
+            }),
+            Event::Step { game, .. } => self.moves = game.moves,
+            Event::AppleEaten { .. } | Event::Frame { .. } | Event::End { .. } => {}
+        }
+    }
+}
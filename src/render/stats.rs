@@ -0,0 +1,171 @@
+use super::{Event, Renderer};
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// Logs a line per apple eaten and a summary when the game ends, including how long the agent
+/// took to decide.
+pub struct Stats {
+    out: Box<dyn Write>,
+    agent: String,
+    decisions: usize,
+    total_decision_time: Duration,
+    max_decision_time: Duration,
+    last_apple: usize,
+}
+
+impl Stats {
+    pub fn stderr() -> Self {
+        Stats::new(Box::new(io::stderr()))
+    }
+
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Stats::new(Box::new(File::create(path)?)))
+    }
+
+    fn new(out: Box<dyn Write>) -> Self {
+        Stats {
+            out,
+            agent: String::new(),
+            decisions: 0,
+            total_decision_time: Duration::ZERO,
+            max_decision_time: Duration::ZERO,
+            last_apple: 0,
+        }
+    }
+
+    fn log(&mut self, event: &Event) -> io::Result<()> {
+        match *event {
+            Event::Start { game, agent, seed } => {
+                // The same stats can log several games, and a game can start part way through.
+                self.agent = agent.to_string();
+                self.decisions = 0;
+                self.total_decision_time = Duration::ZERO;
+                self.max_decision_time = Duration::ZERO;
+                self.last_apple = game.moves;
+                match seed {
+                    Some(seed) => writeln!(self.out, "{} started with seed {}", agent, seed)?,
+                    None => writeln!(self.out, "{} started", agent)?,
+                }
+            }
+            Event::Diagnostics { took, .. } => {
+                self.decisions += 1;
+                self.total_decision_time += took;
+                self.max_decision_time = self.max_decision_time.max(took);
+            }
+            Event::AppleEaten { game } => {
+                writeln!(
+                    self.out,
+                    "apple {} at move {} ({} moves)",
+                    game.score,
+                    game.moves,
+                    game.moves - self.last_apple
+                )?;
+                self.last_apple = game.moves;
+            }
+            Event::End { game, outcome } => {
+                let mean = self
+                    .total_decision_time
+                    .checked_div(self.decisions as u32)
+                    .unwrap_or_default();
+                writeln!(
+                    self.out,
+                    "{} {} with score {} after {} moves ({:.1} moves per apple), \
+                     decisions mean {:.2?} max {:.2?}",
+                    self.agent,
+                    match outcome {
+                        Some(crate::gameplay::Terminal::Won) => "won",
+                        Some(crate::gameplay::Terminal::Died) => "died",
+                        None => "stopped",
+                    },
+                    game.score,
+                    game.moves,
+                    game.moves as f32 / game.score.max(1) as f32,
+                    mean,
+                    self.max_decision_time
+                )?;
+                self.out.flush()?;
+            }
+            Event::Step { .. } | Event::Frame { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Stats {
+    fn handle(&mut self, event: &Event) {
+        self.log(event).expect("failed to write stats");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SnakeGame;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn each_game_starts_its_counts_over() {
+        let out = Shared::default();
+        let mut stats = Stats::new(Box::new(out.clone()));
+        let decide = |took| Event::Diagnostics {
+            took: Duration::from_millis(took),
+            work: Default::default(),
+            plan: None,
+        };
+
+        let first = SnakeGame::seeded(5, 5, 0);
+        stats.handle(&Event::Start {
+            game: &first,
+            agent: "a",
+            seed: Some(0),
+        });
+        stats.handle(&decide(10));
+        stats.handle(&Event::End {
+            game: &first,
+            outcome: None,
+        });
+
+        // Picked up five moves in, as with `--from`.
+        let mut second = SnakeGame::seeded(5, 5, 1);
+        second.moves = 5;
+        stats.handle(&Event::Start {
+            game: &second,
+            agent: "b",
+            seed: None,
+        });
+        stats.handle(&decide(2));
+        second.moves = 8;
+        second.score = 1;
+        stats.handle(&Event::AppleEaten { game: &second });
+        stats.handle(&Event::End {
+            game: &second,
+            outcome: None,
+        });
+
+        let log = String::from_utf8(out.0.take()).unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5, "{}", log);
+        assert!(lines[1].ends_with("decisions mean 10.00ms max 10.00ms"));
+        assert_eq!(lines[3], "apple 1 at move 8 (3 moves)");
+        assert!(lines[4].starts_with("b stopped with score 1 after 8 moves"));
+        assert!(lines[4].ends_with("decisions mean 2.00ms max 2.00ms"));
+    }
+}
@@ -0,0 +1,293 @@
+use super::{path_cells, Event, Renderer};
+use crate::{Action, Heading, SnakeGame};
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write as _},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+const PAGE: &str = include_str!("web.html");
+
+#[derive(Default)]
+struct Shared {
+    frame: String,
+    frame_id: u64,
+    paused: bool,
+    steps: usize,
+    delay: Duration,
+}
+
+type State = Arc<(Mutex<Shared>, Condvar)>;
+
+/// Serves a viewer page on a local port and streams frames to it as server-sent events.
+///
+/// The page can pause, single-step and add a delay between frames. Unlike other renderers this
+/// blocks the runner on each frame, since the viewer is in control of pacing, so it's meant for a
+/// runner with no `sleep_time` of its own.
+pub struct Web {
+    addr: std::net::SocketAddr,
+    state: State,
+    plan: Vec<Action>,
+}
+
+impl Web {
+    pub fn start(addr: impl ToSocketAddrs) -> io::Result<Web> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let state: State = Arc::new((
+            Mutex::new(Shared {
+                ..Default::default()
+            }),
+            Condvar::new(),
+        ));
+
+        let accept_state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = Arc::clone(&accept_state);
+                std::thread::spawn(move || {
+                    // Viewers disconnecting mid-stream is expected, so errors just end the thread.
+                    let _ = handle(stream, &state);
+                });
+            }
+        });
+
+        Ok(Web {
+            addr,
+            state,
+            plan: Vec::new(),
+        })
+    }
+
+    pub fn addr(&self) -> std::net::SocketAddr {
+        self.addr
+    }
+
+    fn publish(&self, game: &SnakeGame, final_: bool) {
+        let frame = frame_json(game, &path_cells(game, &self.plan), final_);
+        let (lock, condvar) = &*self.state;
+        let mut shared = lock.lock().unwrap();
+        shared.frame = frame;
+        shared.frame_id += 1;
+        condvar.notify_all();
+    }
+
+    // Blocks while paused, until the page asks for a step or resumes.
+    fn pace(&self) {
+        let (lock, condvar) = &*self.state;
+        let mut shared = lock.lock().unwrap();
+        while shared.paused && shared.steps == 0 {
+            shared = condvar.wait(shared).unwrap();
+        }
+        if shared.paused {
+            shared.steps -= 1;
+            return;
+        }
+        let delay = shared.delay;
+        drop(shared);
+        std::thread::sleep(delay);
+    }
+}
+
+impl Renderer for Web {
+    fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::Diagnostics { plan, .. } => {
+                self.plan = plan.map(|p| p.to_vec()).unwrap_or_default();
+            }
+            Event::Frame { game } => {
+                self.publish(game, false);
+                self.pace();
+            }
+            Event::End { game, .. } => self.publish(game, true),
+            _ => {}
+        }
+    }
+}
+
+fn frame_json(game: &SnakeGame, plan: &[crate::Cell], final_: bool) -> String {
+    let cells = |cells: &mut dyn Iterator<Item = &crate::Cell>| {
+        cells
+            .map(|c| format!("[{},{}]", c.0, c.1))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let heading = match game.heading {
+        Heading::North => "north",
+        Heading::South => "south",
+        Heading::East => "east",
+        Heading::West => "west",
+    };
+
+    let mut s = String::new();
+    write!(
+        s,
+        r#"{{"width":{},"height":{},"score":{},"moves":{},"heading":"{}","apple":[{},{}],"body":[{}],"plan":[{}],"final":{}}}"#,
+        game.width,
+        game.height,
+        game.score,
+        game.moves,
+        heading,
+        game.apple.0,
+        game.apple.1,
+        cells(&mut game.body.iter()),
+        cells(&mut plan.iter()),
+        final_
+    )
+    .unwrap();
+    s
+}
+
+fn handle(stream: TcpStream, state: &State) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are read and ignored; no endpoint takes a body.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let (lock, condvar) = &**state;
+    match (method, path) {
+        ("GET", "/") => respond(stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        ("GET", "/events") => stream_events(stream, state),
+        ("POST", "/pause" | "/resume" | "/step" | "/speed") => {
+            let mut shared = lock.lock().unwrap();
+            match path {
+                "/pause" => shared.paused = true,
+                "/resume" => {
+                    shared.paused = false;
+                    shared.steps = 0;
+                }
+                // Steps only count while paused, so clicks made while running aren't saved up
+                // and spent at the next pause.
+                "/step" if shared.paused => shared.steps += 1,
+                "/step" => {}
+                _ => {
+                    let ms = query
+                        .split('&')
+                        .find_map(|kv| kv.strip_prefix("ms="))
+                        .and_then(|ms| ms.parse().ok());
+                    match ms {
+                        Some(ms) => shared.delay = Duration::from_millis(ms),
+                        None => {
+                            drop(shared);
+                            return respond(stream, "400 Bad Request", "text/plain", "ms\n");
+                        }
+                    }
+                }
+            }
+            condvar.notify_all();
+            drop(shared);
+            respond(stream, "204 No Content", "text/plain", "")
+        }
+        _ => respond(stream, "404 Not Found", "text/plain", "not found\n"),
+    }
+}
+
+fn respond(mut stream: TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn stream_events(mut stream: TcpStream, state: &State) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+    )?;
+
+    let (lock, condvar) = &**state;
+    let mut seen = 0;
+    loop {
+        let frame = {
+            let mut shared = lock.lock().unwrap();
+            while shared.frame_id == seen {
+                shared = condvar.wait(shared).unwrap();
+            }
+            seen = shared.frame_id;
+            shared.frame.clone()
+        };
+        write!(stream, "data: {}\n\n", frame)?;
+        stream.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn request(web: &Web, method: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(web.addr()).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            method, path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn steps(web: &Web) -> (bool, usize) {
+        let shared = web.state.0.lock().unwrap();
+        (shared.paused, shared.steps)
+    }
+
+    #[test]
+    fn serves_the_page_and_controls() {
+        let web = Web::start("127.0.0.1:0").unwrap();
+
+        let page = request(&web, "GET", "/");
+        assert!(page.starts_with("HTTP/1.1 200 OK\r\n"), "{}", page);
+        assert!(page.ends_with(PAGE));
+        assert!(request(&web, "GET", "/missing").starts_with("HTTP/1.1 404"));
+
+        // Steps taken while running are ignored rather than saved for the next pause.
+        assert!(request(&web, "POST", "/step").starts_with("HTTP/1.1 204"));
+        assert_eq!(steps(&web), (false, 0));
+        request(&web, "POST", "/pause");
+        request(&web, "POST", "/step");
+        assert_eq!(steps(&web), (true, 1));
+        web.pace();
+        assert_eq!(steps(&web), (true, 0));
+        request(&web, "POST", "/step");
+        request(&web, "POST", "/resume");
+        assert_eq!(steps(&web), (false, 0));
+        assert!(request(&web, "POST", "/speed").starts_with("HTTP/1.1 400"));
+    }
+
+    #[test]
+    fn streams_frames_as_events() {
+        let web = Web::start("127.0.0.1:0").unwrap();
+        let game = SnakeGame::seeded(6, 4, 0);
+        web.publish(&game, true);
+
+        let mut stream = TcpStream::connect(web.addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(stream, "GET /events HTTP/1.1\r\n\r\n").unwrap();
+        let mut lines = BufReader::new(stream).lines().map(Result::unwrap);
+        assert_eq!(lines.next().unwrap(), "HTTP/1.1 200 OK");
+        let data = lines.find_map(|l| l.strip_prefix("data: ").map(str::to_string));
+        assert_eq!(data.unwrap(), frame_json(&game, &[], true));
+    }
+}
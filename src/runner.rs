@@ -0,0 +1,213 @@
+use crate::{
+    agents::Agent,
+    gameplay::{Action, SnakeGame, Terminal},
+    profile::Work,
+    recording::Recording,
+    render::{Event, Renderer},
+};
+
+use std::time::{Duration, Instant};
+
+/// Plays games and reports them to a renderer, deciding which moves get drawn and how fast.
+///
+/// Every move is reported as a `Step`, but only every `render_every`th move produces a `Frame`,
+/// followed by a pause of `sleep_time`. A `render_every` of 0 draws every move.
+pub struct Runner {
+    pub render_every: usize,
+    pub sleep_time: Duration,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Runner {
+            render_every: 1,
+            sleep_time: Duration::from_millis(10),
+        }
+    }
+}
+
+impl Runner {
+    /// Plays `game` to the end with `agent`. `seed` is the seed `game` was created with, if any.
+    pub fn run(
+        &self,
+        game: &mut SnakeGame,
+        seed: Option<u64>,
+        agent: &mut dyn Agent,
+        name: &str,
+        renderer: &mut dyn Renderer,
+    ) -> Terminal {
+        renderer.handle(&Event::Start {
+            game,
+            agent: name,
+            seed,
+        });
+        self.frame(game, renderer);
+
+        loop {
+            let action = {
+                #[cfg(feature = "trace")]
+                let _span = tracing::info_span!("decision", agent = name, seed, moves = game.moves)
+                    .entered();
+                // Drops anything counted outside the agent.
+                Work::take();
+                let start = Instant::now();
+                let action = agent.action(game);
+                let (took, work) = (start.elapsed(), Work::take());
+                #[cfg(feature = "trace")]
+                tracing::debug!(
+                    ?took,
+                    nodes = work.nodes,
+                    clones = work.clones,
+                    bfs = work.bfs,
+                    "decided"
+                );
+                renderer.handle(&Event::Diagnostics {
+                    took,
+                    work,
+                    plan: agent.plan().as_deref(),
+                });
+                action
+            };
+
+            if let Some(outcome) = self.step(game, action, renderer) {
+                return outcome;
+            }
+        }
+    }
+
+    /// Reports a recorded game as if it were being played, without agent diagnostics.
+    pub fn replay(&self, recording: &Recording, name: &str, renderer: &mut dyn Renderer) {
+        let mut game = recording.start();
+        renderer.handle(&Event::Start {
+            game: &game,
+            agent: name,
+            seed: Some(recording.seed),
+        });
+        self.frame(&game, renderer);
+
+        for &action in &recording.actions {
+            if self.step(&mut game, action, renderer).is_some() {
+                return;
+            }
+        }
+        // Recordings cut short still need their renderers finished.
+        renderer.handle(&Event::End {
+            game: &game,
+            outcome: None,
+        });
+    }
+
+    fn step(
+        &self,
+        game: &mut SnakeGame,
+        action: Action,
+        renderer: &mut dyn Renderer,
+    ) -> Option<Terminal> {
+        let score = game.score;
+        let outcome = game.do_action(action);
+        renderer.handle(&Event::Step { game, action });
+        if game.score > score {
+            renderer.handle(&Event::AppleEaten { game });
+        }
+
+        match outcome {
+            Some(outcome) => {
+                renderer.handle(&Event::End {
+                    game,
+                    outcome: Some(outcome),
+                });
+                Some(outcome)
+            }
+            None => {
+                if self.render_every == 0 || game.moves.is_multiple_of(self.render_every) {
+                    self.frame(game, renderer);
+                }
+                None
+            }
+        }
+    }
+
+    fn frame(&self, game: &SnakeGame, renderer: &mut dyn Renderer) {
+        renderer.handle(&Event::Frame { game });
+        std::thread::sleep(self.sleep_time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agents::greedy::Greedy, Rules, Spawn};
+
+    // Each event as its name and the moves it happened at.
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Renderer for Log {
+        fn handle(&mut self, event: &Event) {
+            self.0.push(match event {
+                Event::Start { game, .. } => format!("Start {}", game.moves),
+                Event::Diagnostics { .. } => "Diagnostics".to_string(),
+                Event::Step { game, action } => format!("Step {} {:?}", game.moves, action),
+                Event::AppleEaten { game } => format!("AppleEaten {}", game.moves),
+                Event::Frame { game } => format!("Frame {}", game.moves),
+                Event::End { game, outcome } => format!("End {} {:?}", game.moves, outcome),
+            });
+        }
+    }
+
+    // Greedy, since the runner has no move limit and tree search can circle a small board forever.
+    #[test]
+    fn reports_moves_in_order_and_replays_them() {
+        let runner = Runner {
+            render_every: 2,
+            sleep_time: Duration::ZERO,
+        };
+        let mut recording = Recording::new(5, 5, 1, Rules::default(), Spawn::default());
+        let mut game = recording.start();
+        let mut log = Log::default();
+        let outcome = runner.run(&mut game, Some(1), &mut Greedy, "", &mut log);
+        let log = log.0;
+
+        assert_eq!(log[..2], ["Start 0", "Frame 0"]);
+        let mut events = log[2..].iter().map(String::as_str).peekable();
+        let mut apples = 0;
+        for moves in 1..=game.moves {
+            assert_eq!(events.next(), Some("Diagnostics"));
+            let step = events.next().unwrap();
+            let action = step
+                .strip_prefix(&format!("Step {} ", moves))
+                .unwrap_or_else(|| panic!("expected step {}, got {:?}", moves, step));
+            recording.actions.push(match action {
+                "GoStraight" => Action::GoStraight,
+                "TurnLeft" => Action::TurnLeft,
+                _ => Action::TurnRight,
+            });
+            if events
+                .next_if_eq(&&*format!("AppleEaten {}", moves))
+                .is_some()
+            {
+                apples += 1;
+            }
+            if moves == game.moves {
+                assert_eq!(
+                    events.next(),
+                    Some(&*format!("End {} {:?}", moves, Some(outcome)))
+                );
+            } else if moves % 2 == 0 {
+                assert_eq!(events.next(), Some(&*format!("Frame {}", moves)));
+            }
+        }
+        assert_eq!(events.next(), None);
+        assert!(game.score > 0);
+        assert_eq!(apples, game.score);
+
+        // A replay reports the same events, without any from an agent.
+        let mut replayed = Log::default();
+        runner.replay(&recording, "", &mut replayed);
+        let played = log
+            .into_iter()
+            .filter(|e| e != "Diagnostics")
+            .collect::<Vec<_>>();
+        assert_eq!(replayed.0, played);
+    }
+}
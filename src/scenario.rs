@@ -0,0 +1,358 @@
+use crate::{agents::Agent, gameplay::*};
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// A hand-made position with outcomes some agents are expected to reach from it.
+///
+/// Fixtures are `key: value` lines followed by the board as `SnakeGame`'s `Display` writes it:
+///
+/// ```text
+/// # Comments start with `#` before the board.
+/// rules: tail-follow
+/// seed: 7
+/// expect: tree-search, average-path survive 30
+/// expect: tree-search eat 12
+/// 2/40
+/// ######
+/// #    #
+/// # ++>#
+/// #   O#
+/// ######
+/// ```
+///
+/// `rules` and `seed`, for placing later apples, are optional. See `SnakeGame::from_board` for
+/// when the board needs a `body:` line.
+///
+/// The `scenarios` command plays every agent in `agents::PLAYABLE` on every fixture, and the
+/// others only on fixtures whose expectations name them: `simple-path` only handles boards of
+/// even height with the body on its cycle, and `random`'s moves aren't seeded, so it can only be
+/// held to outcomes that every sequence of moves reaches.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub name: String,
+    pub game: SnakeGame,
+    /// Places apples after the first, so the game can be set up again from the fixture.
+    pub seed: u64,
+    pub expectations: Vec<Expectation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectation {
+    pub agents: Vec<String>,
+    pub goal: Goal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// Don't die in the next this many moves. Winning counts.
+    Survive(usize),
+    /// Eat an apple within this many moves.
+    Eat(usize),
+}
+
+/// What happened when an agent played a scenario for a number of moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Run {
+    pub moves: usize,
+    pub won: bool,
+    pub died_at: Option<usize>,
+    pub ate_at: Option<usize>,
+}
+
+impl Goal {
+    pub fn moves(self) -> usize {
+        match self {
+            Goal::Survive(n) | Goal::Eat(n) => n,
+        }
+    }
+
+    pub fn check(self, run: &Run) -> Result<(), String> {
+        match self {
+            Goal::Survive(n) => match run.died_at {
+                Some(at) if at <= n => Err(format!("died at move {} of {}", at, n)),
+                None if !run.won && run.moves < n => {
+                    Err(format!("only played {} of {} moves", run.moves, n))
+                }
+                _ => Ok(()),
+            },
+            Goal::Eat(n) => match (run.ate_at, run.died_at) {
+                (Some(at), _) if at <= n => Ok(()),
+                (_, Some(at)) if at <= n => Err(format!("died at move {} before eating", at)),
+                _ => Err(format!("didn't eat within {} moves", n)),
+            },
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Scenario> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let text = std::fs::read_to_string(path)?;
+        Scenario::parse(&name, &text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Every `.txt` scenario in `dir`, sorted by name.
+    pub fn load_dir(dir: impl AsRef<Path>) -> io::Result<Vec<Scenario>> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<PathBuf>>>()?;
+        paths.retain(|p| p.extension().is_some_and(|e| e == "txt"));
+        paths.sort();
+        paths.iter().map(Scenario::load).collect()
+    }
+
+    pub fn parse(name: &str, text: &str) -> Result<Scenario, String> {
+        let mut lines = text.lines().enumerate().peekable();
+        let mut rules = Rules::default();
+        let mut seed = 0;
+        let mut expectations = Vec::new();
+
+        // Header lines run until the `score/moves` line the board starts with.
+        while let Some((i, line)) = lines.next_if(|(_, l)| !is_score_line(l)) {
+            let at = |e: String| format!("line {}: {}", i + 1, e);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| at(format!("expected `key: value`, got {:?}", line)))?;
+            let value = value.trim();
+            match key.trim() {
+                "rules" => rules = value.parse().map_err(at)?,
+                "seed" => seed = value.parse().map_err(|_| at("invalid seed".to_string()))?,
+                "expect" => expectations.push(parse_expectation(value).map_err(at)?),
+                key => return Err(at(format!("unknown key {:?}", key))),
+            }
+        }
+
+        let (first, _) = lines.peek().copied().ok_or("missing board")?;
+        let board = lines.map(|(_, l)| l).collect::<Vec<_>>().join("\n");
+        let game = SnakeGame::from_board(&board, seed, rules)
+            .map_err(|e| format!("board at line {}: {}", first + 1, e))?;
+
+        Ok(Scenario {
+            name: name.to_string(),
+            game,
+            seed,
+            expectations,
+        })
+    }
+
+    /// Plays `agent` from the scenario's position for up to `moves` moves.
+    pub fn run(&self, agent: &mut dyn Agent, moves: usize) -> Run {
+        let mut game = self.game.clone();
+        let mut run = Run::default();
+        while run.moves < moves {
+            let score = game.score;
+            let outcome = game.do_action(agent.action(&game));
+            run.moves += 1;
+            if game.score > score && run.ate_at.is_none() {
+                run.ate_at = Some(run.moves);
+            }
+            match outcome {
+                Some(Terminal::Died) => {
+                    run.died_at = Some(run.moves);
+                    break;
+                }
+                Some(Terminal::Won) => {
+                    run.won = true;
+                    break;
+                }
+                None => {}
+            }
+        }
+        run
+    }
+
+    /// How many moves to play so every expectation can be checked from one run.
+    pub fn horizon(&self) -> usize {
+        self.expectations
+            .iter()
+            .map(|e| e.goal.moves())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// `<agent>[, <agent>...] survive|eat <moves>`
+fn parse_expectation(s: &str) -> Result<Expectation, String> {
+    let mut words = s.rsplitn(3, ' ');
+    let (moves, goal, agents) = match (words.next(), words.next(), words.next()) {
+        (Some(moves), Some(goal), Some(agents)) => (moves, goal, agents),
+        _ => {
+            return Err(format!(
+                "expected `<agents> survive|eat <moves>`, got {:?}",
+                s
+            ))
+        }
+    };
+    let moves = moves
+        .parse()
+        .map_err(|_| format!("invalid moves {:?}", moves))?;
+    let goal = match goal {
+        "survive" => Goal::Survive(moves),
+        "eat" => Goal::Eat(moves),
+        _ => return Err(format!("unknown goal {:?}", goal)),
+    };
+    let agents = agents
+        .split(',')
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+    Ok(Expectation { agents, goal })
+}
+
+/// The fixture `parse` reads, without comments.
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.game.rules != Rules::default() {
+            writeln!(f, "rules: {}", self.game.rules)?;
+        }
+        writeln!(f, "seed: {}", self.seed)?;
+        for expectation in &self.expectations {
+            writeln!(f, "expect: {}", expectation)?;
+        }
+        write!(f, "{}", self.game)
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (goal, moves) = match self.goal {
+            Goal::Survive(n) => ("survive", n),
+            Goal::Eat(n) => ("eat", n),
+        };
+        write!(f, "{} {} {}", self.agents.join(", "), goal, moves)
+    }
+}
+
+fn is_score_line(line: &str) -> bool {
+    line.trim().split_once('/').is_some_and(|(score, moves)| {
+        [score, moves]
+            .iter()
+            .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agents, agents::heuristic::HeuristicConfig};
+
+    #[test]
+    fn parses_the_documented_example() {
+        let text = "\
+# Comments start with `#` before the board.
+rules: tail-follow
+seed: 7
+expect: tree-search, average-path survive 30
+expect: tree-search eat 12
+2/40
+######
+#    #
+# ++>#
+#   O#
+######";
+        let scenario = Scenario::parse("example", text).unwrap();
+        assert_eq!(scenario.game.body, [Cell(1, 1), Cell(2, 1), Cell(3, 1)]);
+        assert_eq!(scenario.game.heading, Heading::East);
+        assert!(scenario.game.rules.tail_follow);
+        assert_eq!((scenario.game.score, scenario.game.moves), (2, 40));
+        assert_eq!(
+            scenario.expectations,
+            [
+                Expectation {
+                    agents: vec!["tree-search".into(), "average-path".into()],
+                    goal: Goal::Survive(30),
+                },
+                Expectation {
+                    agents: vec!["tree-search".into()],
+                    goal: Goal::Eat(12),
+                },
+            ]
+        );
+        assert_eq!(
+            scenario.game.to_string(),
+            text.lines().skip(5).collect::<Vec<_>>().join("\n")
+        );
+        assert_eq!(
+            scenario.to_string(),
+            text.lines().skip(1).collect::<Vec<_>>().join("\n")
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        let text = "seed: 1\nexpect: greedy survive 3\n0/0\n#####\n#+ >#\n#  O#\n#####";
+        let err = Scenario::parse("bad", text).unwrap_err();
+        assert!(err.starts_with("board at line 3: "), "{}", err);
+        let err = Scenario::parse("bad", "seed: x\n0/0").unwrap_err();
+        assert!(err.starts_with("line 1: "), "{}", err);
+    }
+
+    #[test]
+    fn goals() {
+        let run = Run {
+            moves: 20,
+            ate_at: Some(8),
+            ..Default::default()
+        };
+        assert!(Goal::Survive(20).check(&run).is_ok());
+        assert!(Goal::Survive(21).check(&run).is_err());
+        assert!(Goal::Eat(8).check(&run).is_ok());
+        assert!(Goal::Eat(7).check(&run).is_err());
+
+        let died = Run {
+            moves: 5,
+            died_at: Some(5),
+            ..Default::default()
+        };
+        assert!(Goal::Survive(4).check(&died).is_ok());
+        assert!(Goal::Survive(5).check(&died).is_err());
+        assert!(Goal::Eat(10).check(&died).is_err());
+
+        let won = Run {
+            moves: 5,
+            won: true,
+            ate_at: Some(1),
+            ..Default::default()
+        };
+        assert!(Goal::Survive(30).check(&won).is_ok());
+    }
+
+    // Every expectation in the fixtures holds with the default heuristics.
+    #[test]
+    fn fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let scenarios = Scenario::load_dir(dir).unwrap();
+        assert!(!scenarios.is_empty());
+
+        let mut failures = Vec::new();
+        for scenario in &scenarios {
+            for expectation in &scenario.expectations {
+                for name in &expectation.agents {
+                    let mut agent = agents::by_name(name, &HeuristicConfig::default())
+                        .unwrap_or_else(|| panic!("unknown agent {:?}", name));
+                    let run = scenario.run(agent.as_mut(), expectation.goal.moves());
+                    if let Err(e) = expectation.goal.check(&run) {
+                        failures.push(format!("{} {}: {}", scenario.name, name, e));
+                    }
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
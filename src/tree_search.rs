@@ -34,6 +34,7 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         let ret = self.queue.pop()?;
+        crate::profile::Work::count(|w| w.nodes += 1);
         for child in (self.expander)(&ret) {
             self.queue.insert((self.ranker)(&child), child);
         }